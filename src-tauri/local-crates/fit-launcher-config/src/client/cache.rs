@@ -0,0 +1,429 @@
+//! On-disk HTTP cache driven by conditional requests.
+//!
+//! Every page fetched through [`cached_get`] keeps its body along with the
+//! `ETag`/`Last-Modified` validators, the next request for the same URL sends
+//! them back and a `304 Not Modified` is answered from disk.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, Utc};
+use directories::BaseDirs;
+use once_cell::sync::Lazy;
+use reqwest::{
+    Response, StatusCode,
+    header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::{error, info, warn};
+
+use crate::client::{
+    connectivity::CONNECTIVITY,
+    http::{HTTP_CLIENT, HttpError},
+};
+use crate::settings::config::SettingsConfigurationError;
+
+/// Total size of the cached bodies, the least recently used are evicted past it.
+pub const HTTP_CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+/// Bodies bigger than this are never cached.
+pub const HTTP_CACHE_MAX_ENTRY_BYTES: u64 = 8 * 1024 * 1024;
+
+const INDEX_FILE_NAME: &str = "index.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpCacheEntry {
+    pub url: String,
+    file_name: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub size: u64,
+    /// Unix timestamps, in seconds.
+    pub stored_at: u64,
+    pub last_access: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HttpCacheIndex {
+    entries: HashMap<String, HttpCacheEntry>,
+}
+
+impl HttpCacheIndex {
+    fn total_bytes(&self) -> u64 {
+        self.entries.values().map(|entry| entry.size).sum()
+    }
+}
+
+/// A response body and whether it came from the cache after a `304`.
+#[derive(Debug)]
+pub struct CachedResponse {
+    pub body: String,
+    /// The server confirmed nothing changed since the cached copy, callers
+    /// that already parsed this page can skip it.
+    pub not_modified: bool,
+    /// Set when the site couldn't be asked and this is the cached copy, to
+    /// when it was stored.
+    pub stale_since: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HttpCacheReport {
+    pub directory: PathBuf,
+    pub total_bytes: u64,
+    pub max_bytes: u64,
+    pub entries: Vec<HttpCacheEntry>,
+}
+
+pub struct HttpCache {
+    directory: PathBuf,
+    /// Loaded from disk on first use.
+    index: Mutex<Option<HttpCacheIndex>>,
+}
+
+pub static HTTP_CACHE: Lazy<HttpCache> = Lazy::new(|| {
+    let directory = BaseDirs::new()
+        .expect("Failed to determine base directories")
+        .cache_dir()
+        .join("com.fitlauncher.carrotrub")
+        .join("http_cache");
+
+    HttpCache::new(directory)
+});
+
+/// Seconds since the Unix epoch, what every cache of the app timestamps its entries with.
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Named after the SHA-256 of the URL, two URLs never share a body file.
+fn body_file_name(url: &str) -> String {
+    format!("{:x}.body", Sha256::digest(url.as_bytes()))
+}
+
+impl HttpCache {
+    pub fn new(directory: PathBuf) -> Self {
+        HttpCache {
+            directory,
+            index: Mutex::new(None),
+        }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    async fn index(&self) -> MutexGuard<'_, Option<HttpCacheIndex>> {
+        let mut guard = self.index.lock().await;
+
+        if guard.is_none() {
+            let index_path = self.directory.join(INDEX_FILE_NAME);
+            let index = match tokio::fs::read_to_string(&index_path).await {
+                Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                    warn!("HTTP cache index is corrupted, starting over: {}", e);
+                    HttpCacheIndex::default()
+                }),
+                Err(_) => HttpCacheIndex::default(),
+            };
+            *guard = Some(index);
+        }
+
+        guard
+    }
+
+    async fn save_index(&self, index: &HttpCacheIndex) -> std::io::Result<()> {
+        tokio::fs::create_dir_all(&self.directory).await?;
+
+        let index_path = self.directory.join(INDEX_FILE_NAME);
+        let temp_path = self.directory.join("index.tmp.json");
+        tokio::fs::write(&temp_path, serde_json::to_vec(index)?).await?;
+        tokio::fs::rename(&temp_path, &index_path).await
+    }
+
+    /// Validators of the cached copy of `url`, if its body is still on disk.
+    async fn lookup(&self, url: &str) -> Option<HttpCacheEntry> {
+        let guard = self.index().await;
+        let entry = guard.as_ref()?.entries.get(url)?.clone();
+
+        tokio::fs::try_exists(self.directory.join(&entry.file_name))
+            .await
+            .unwrap_or(false)
+            .then_some(entry)
+    }
+
+    /// The cached body of `entry`.
+    ///
+    /// The access time is only kept in memory, it is saved with the next stored page.
+    async fn read_body(&self, entry: &HttpCacheEntry) -> std::io::Result<String> {
+        let body = tokio::fs::read_to_string(self.directory.join(&entry.file_name)).await?;
+
+        let mut guard = self.index().await;
+        if let Some(cached) = guard
+            .as_mut()
+            .and_then(|index| index.entries.get_mut(&entry.url))
+        {
+            cached.last_access = now_secs();
+        }
+
+        Ok(body)
+    }
+
+    async fn store(
+        &self,
+        url: &str,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        body: &str,
+    ) -> std::io::Result<()> {
+        let size = body.len() as u64;
+        if size > HTTP_CACHE_MAX_ENTRY_BYTES {
+            return Ok(());
+        }
+
+        let mut guard = self.index().await;
+        let index = guard.get_or_insert_with(HttpCacheIndex::default);
+
+        tokio::fs::create_dir_all(&self.directory).await?;
+        let file_name = body_file_name(url);
+        tokio::fs::write(self.directory.join(&file_name), body).await?;
+
+        let now = now_secs();
+        let replaced = index.entries.insert(
+            url.to_string(),
+            HttpCacheEntry {
+                url: url.to_string(),
+                file_name: file_name.clone(),
+                etag,
+                last_modified,
+                size,
+                stored_at: now,
+                last_access: now,
+            },
+        );
+
+        // Entries of older versions were named after another hash, don't leave them behind.
+        if let Some(replaced) = replaced.filter(|replaced| replaced.file_name != file_name)
+            && let Err(e) = tokio::fs::remove_file(self.directory.join(&replaced.file_name)).await
+        {
+            warn!("Failed to remove the old cached body of {}: {}", url, e);
+        }
+
+        self.evict_over_limit(index).await;
+        self.save_index(index).await
+    }
+
+    /// Drop the least recently used entries until the cache fits its limit.
+    async fn evict_over_limit(&self, index: &mut HttpCacheIndex) {
+        let mut total_bytes = index.total_bytes();
+        if total_bytes <= HTTP_CACHE_MAX_BYTES {
+            return;
+        }
+
+        let mut by_access: Vec<(u64, String)> = index
+            .entries
+            .values()
+            .map(|entry| (entry.last_access, entry.url.clone()))
+            .collect();
+        by_access.sort();
+
+        for (_, url) in by_access {
+            if total_bytes <= HTTP_CACHE_MAX_BYTES {
+                break;
+            }
+            if let Some(entry) = index.entries.remove(&url) {
+                total_bytes -= entry.size;
+                if let Err(e) = tokio::fs::remove_file(self.directory.join(&entry.file_name)).await
+                {
+                    warn!("Failed to evict cached body of {}: {}", url, e);
+                }
+            }
+        }
+    }
+
+    pub async fn report(&self) -> HttpCacheReport {
+        let guard = self.index().await;
+        let mut entries: Vec<HttpCacheEntry> = guard
+            .as_ref()
+            .map(|index| index.entries.values().cloned().collect())
+            .unwrap_or_default();
+        entries.sort_by(|a, b| b.last_access.cmp(&a.last_access));
+
+        HttpCacheReport {
+            directory: self.directory.clone(),
+            total_bytes: entries.iter().map(|entry| entry.size).sum(),
+            max_bytes: HTTP_CACHE_MAX_BYTES,
+            entries,
+        }
+    }
+
+    /// Forget the cached copy of `url`, the next request fetches it in full.
+    pub async fn remove(&self, url: &str) -> std::io::Result<()> {
+        let mut guard = self.index().await;
+        let Some(index) = guard.as_mut() else {
+            return Ok(());
+        };
+        let Some(entry) = index.entries.remove(url) else {
+            return Ok(());
+        };
+
+        match tokio::fs::remove_file(self.directory.join(&entry.file_name)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        self.save_index(index).await
+    }
+
+    pub async fn clear(&self) -> std::io::Result<()> {
+        let mut guard = self.index.lock().await;
+        *guard = Some(HttpCacheIndex::default());
+
+        match tokio::fs::remove_dir_all(&self.directory).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Turn any status but a success into an error.
+fn success(response: Response) -> Result<Response, HttpError> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(HttpError::Status {
+            url: response.url().to_string(),
+            status,
+        })
+    }
+}
+
+/// GET `url` through [`HTTP_CLIENT`], revalidating the cached copy if there is one.
+///
+/// While the site can't be reached the cached copy is served as is, and so
+/// it is when the request fails in a way that may go away.
+pub async fn cached_get(url: &str) -> Result<CachedResponse, HttpError> {
+    let cached = HTTP_CACHE.lookup(url).await;
+
+    if CONNECTIVITY.is_offline() {
+        return match &cached {
+            Some(entry) => serve_stale(entry).await,
+            None => Err(HttpError::Offline {
+                url: url.to_string(),
+            }),
+        };
+    }
+
+    match (fetch_revalidated(url, cached.as_ref()).await, &cached) {
+        (Err(e), Some(entry)) if e.is_transient() => {
+            warn!("{}, serving the cached copy", e);
+            serve_stale(entry).await.map_err(|_| e)
+        }
+        (result, _) => result,
+    }
+}
+
+async fn serve_stale(entry: &HttpCacheEntry) -> Result<CachedResponse, HttpError> {
+    let body = HTTP_CACHE
+        .read_body(entry)
+        .await
+        .map_err(|_| HttpError::Offline {
+            url: entry.url.clone(),
+        })?;
+
+    Ok(CachedResponse {
+        body,
+        not_modified: false,
+        stale_since: Some(
+            DateTime::from_timestamp(entry.stored_at as i64, 0).unwrap_or_else(Utc::now),
+        ),
+    })
+}
+
+async fn fetch_revalidated(
+    url: &str,
+    cached: Option<&HttpCacheEntry>,
+) -> Result<CachedResponse, HttpError> {
+    let mut request = HTTP_CLIENT.get(url);
+    if let Some(entry) = cached {
+        if let Some(etag) = &entry.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = HTTP_CLIENT.send(request).await?;
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let Some(entry) = cached {
+            match HTTP_CACHE.read_body(entry).await {
+                Ok(body) => {
+                    return Ok(CachedResponse {
+                        body,
+                        not_modified: true,
+                        stale_since: None,
+                    });
+                }
+                Err(e) => {
+                    // The body vanished between the lookup and now, ask again without validators.
+                    warn!("Cached body of {} is unreadable: {}", url, e);
+                    let body = success(HTTP_CLIENT.send(HTTP_CLIENT.get(url)).await?)?
+                        .text()
+                        .await
+                        .map_err(HttpError::request)?;
+                    return Ok(CachedResponse {
+                        body,
+                        not_modified: false,
+                        stale_since: None,
+                    });
+                }
+            }
+        }
+    }
+
+    let response = success(response)?;
+    let headers = response.headers();
+    let header_value = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header_value(ETAG);
+    let last_modified = header_value(LAST_MODIFIED);
+
+    let body = response.text().await.map_err(HttpError::request)?;
+
+    // Without validators there is nothing to revalidate, so nothing worth keeping.
+    if (etag.is_some() || last_modified.is_some())
+        && let Err(e) = HTTP_CACHE.store(url, etag, last_modified, &body).await
+    {
+        error!("Failed to cache the response of {}: {}", url, e);
+    }
+
+    Ok(CachedResponse {
+        body,
+        not_modified: false,
+        stale_since: None,
+    })
+}
+
+/// Every cached page with its validators and the total size on disk.
+#[tauri::command]
+pub async fn inspect_http_cache() -> HttpCacheReport {
+    HTTP_CACHE.report().await
+}
+
+#[tauri::command]
+pub async fn clear_http_cache() -> Result<(), SettingsConfigurationError> {
+    HTTP_CACHE.clear().await?;
+    info!("HTTP cache cleared");
+    Ok(())
+}
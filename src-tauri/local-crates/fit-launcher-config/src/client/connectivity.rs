@@ -0,0 +1,164 @@
+//! Whether the repack site can be reached.
+//!
+//! The state is only changed by probes, see [`ConnectivityMonitor::probe`].
+//! Requests failing to connect through [`crate::client::http::HTTP_CLIENT`]
+//! and requests answered while offline wake the watcher up so it probes
+//! right away instead of on its next interval.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::{Notify, watch};
+use tracing::{info, warn};
+
+use crate::client::dns::CUSTOM_DNS_CLIENT;
+
+/// How long a probe waits for an answer.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+/// A probe only fails when every attempt did, a single lost request isn't an outage.
+const PROBE_ATTEMPTS: u32 = 2;
+const PROBE_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Connectivity {
+    /// No probe finished yet.
+    Unknown,
+    Online {
+        since: DateTime<Utc>,
+    },
+    Offline {
+        since: DateTime<Utc>,
+    },
+}
+
+impl Connectivity {
+    pub fn is_offline(&self) -> bool {
+        matches!(self, Connectivity::Offline { .. })
+    }
+}
+
+pub struct ConnectivityMonitor {
+    state: watch::Sender<Connectivity>,
+    /// Woken by requests hinting the state is wrong.
+    suspicion: Notify,
+}
+
+pub static CONNECTIVITY: Lazy<ConnectivityMonitor> = Lazy::new(|| ConnectivityMonitor {
+    state: watch::Sender::new(Connectivity::Unknown),
+    suspicion: Notify::new(),
+});
+
+impl ConnectivityMonitor {
+    pub fn state(&self) -> Connectivity {
+        *self.state.borrow()
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.state().is_offline()
+    }
+
+    /// When the site stopped answering, `None` while it can be reached.
+    pub fn offline_since(&self) -> Option<DateTime<Utc>> {
+        match self.state() {
+            Connectivity::Offline { since } => Some(since),
+            _ => None,
+        }
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Connectivity> {
+        self.state.subscribe()
+    }
+
+    /// A request couldn't connect, the site may be gone.
+    pub(crate) fn report_unreachable(&self) {
+        if !self.is_offline() {
+            self.suspicion.notify_one();
+        }
+    }
+
+    /// A request got an answer, the site may be back.
+    pub(crate) fn report_reachable(&self) {
+        if self.is_offline() {
+            self.suspicion.notify_one();
+        }
+    }
+
+    /// Wait until a request hints the state changed, or `timeout` passed.
+    pub async fn wait_for_suspicion(&self, timeout: Duration) {
+        let _ = tokio::time::timeout(timeout, self.suspicion.notified()).await;
+    }
+
+    /// Check whether `url` answers and update the state.
+    ///
+    /// Any HTTP answer counts as reachable, an error page still means the
+    /// network works. Returns the new state when it changed.
+    pub async fn probe(&self, url: &str) -> Option<Connectivity> {
+        let mut reachable = false;
+        for attempt in 1..=PROBE_ATTEMPTS {
+            match CUSTOM_DNS_CLIENT
+                .head(url)
+                .timeout(PROBE_TIMEOUT)
+                .send()
+                .await
+            {
+                Ok(_) => {
+                    reachable = true;
+                    break;
+                }
+                Err(e) => warn!(
+                    "Connectivity probe {}/{} of {} failed: {}",
+                    attempt, PROBE_ATTEMPTS, url, e
+                ),
+            }
+            if attempt < PROBE_ATTEMPTS {
+                tokio::time::sleep(PROBE_RETRY_DELAY).await;
+            }
+        }
+
+        self.record(reachable)
+    }
+
+    fn record(&self, reachable: bool) -> Option<Connectivity> {
+        let mut changed = None;
+        self.state.send_if_modified(|state| {
+            let next = match (*state, reachable) {
+                (Connectivity::Online { .. }, true) | (Connectivity::Offline { .. }, false) => {
+                    return false;
+                }
+                (_, true) => Connectivity::Online { since: Utc::now() },
+                (_, false) => Connectivity::Offline { since: Utc::now() },
+            };
+            info!("Connectivity changed from {:?} to {:?}", state, next);
+            *state = next;
+            changed = Some(next);
+            true
+        });
+        changed
+    }
+}
+
+/// What a command answers, `stale_since` is set while the site can't be
+/// reached and `data` is the last one that could be fetched.
+#[derive(Debug, Clone, Serialize)]
+pub struct Served<T> {
+    pub data: T,
+    pub stale_since: Option<DateTime<Utc>>,
+}
+
+impl<T> Served<T> {
+    /// `data` as it was last refreshed at `refreshed_at`, if that's known.
+    pub fn new(data: T, refreshed_at: Option<DateTime<Utc>>) -> Self {
+        let stale_since = CONNECTIVITY
+            .offline_since()
+            .map(|since| refreshed_at.map_or(since, |refreshed_at| refreshed_at.min(since)));
+        Served { data, stale_since }
+    }
+}
+
+#[tauri::command]
+pub fn get_connectivity() -> Connectivity {
+    CONNECTIVITY.state()
+}
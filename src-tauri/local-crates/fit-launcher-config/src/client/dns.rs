@@ -1,4 +1,3 @@
-use directories::BaseDirs;
 use hickory_resolver::TokioAsyncResolver;
 use hickory_resolver::config::*;
 use once_cell::sync::Lazy;
@@ -8,14 +7,14 @@ use reqwest::{
     dns::{Addrs, Name, Resolve, Resolving},
 };
 use serde::{Deserialize, Serialize};
-use std::fs;
 use std::io;
-use std::io::Write;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use tracing::error;
 use tracing::info;
 
+use crate::settings::migration::{VersionedSettings, load_settings, save_settings};
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FitLauncherDnsConfig {
     system_conf: bool,
@@ -49,41 +48,24 @@ impl FitLauncherDnsConfig {
 }
 
 fn ensure_and_load_dns_config() -> FitLauncherDnsConfig {
-    let base_dirs = BaseDirs::new().expect("Failed to determine base directories");
-    let config_path = base_dirs
-        .config_dir()
-        .join("com.fitlauncher.carrotrub")
-        .join("fitgirlConfig")
-        .join("settings")
-        .join("dns");
-
-    if !config_path.exists() {
-        fs::create_dir_all(&config_path).expect("Failed to create DNS config directory");
-    }
-
-    let config_file = config_path.join("dns.json");
-    if !config_file.exists() {
-        let default_config = FitLauncherDnsConfig::default();
-
-        let default_config_data = serde_json::to_string_pretty(&default_config)
-            .expect("Failed to serialize default DNS config");
-
-        let mut file = fs::File::create(&config_file).expect("Failed to create dns.json file");
-        file.write_all(default_config_data.as_bytes())
-            .expect("Failed to write to dns.json file");
-    }
-
-    let config_data =
-        fs::read_to_string(config_file).expect("Failed to read dns.json configuration file");
-    let mut dns_config: FitLauncherDnsConfig = match serde_json::from_str(&config_data) {
+    let mut dns_config = match load_settings::<FitLauncherDnsConfig>() {
         Ok(conf) => conf,
         Err(e) => {
-            error!("Error serializing dns config from file : {}", e);
-            error!("Using old config, updating...");
+            // The file is left as is, this run goes on with the default resolver.
+            error!("Error loading the dns config, using the default one: {}", e);
             FitLauncherDnsConfig::default()
         }
     };
 
+    match FitLauncherDnsConfig::file_path() {
+        Ok(config_file) if !config_file.exists() => {
+            if let Err(e) = save_settings(&dns_config) {
+                error!("Failed to write the default dns config: {}", e);
+            }
+        }
+        _ => {}
+    }
+
     if dns_config.system_conf {
         dns_config = FitLauncherDnsConfig::default_system();
     }
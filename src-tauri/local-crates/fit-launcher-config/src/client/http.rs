@@ -0,0 +1,358 @@
+//! Retries and rate limiting on top of [`CUSTOM_DNS_CLIENT`].
+//!
+//! Every request sent through [`HTTP_CLIENT`] is retried with an exponential
+//! backoff on network errors and `429`/`5xx` answers. A `Retry-After` pauses
+//! the whole host, and each host gets a cap on requests in flight and a
+//! per-minute request budget so a scrape can't hammer a site.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use reqwest::{
+    Client, RequestBuilder, Response, StatusCode,
+    header::{HeaderMap, RETRY_AFTER},
+};
+use serde::Serialize;
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+use crate::client::{connectivity::CONNECTIVITY, dns::CUSTOM_DNS_CLIENT};
+
+/// The window the request budget of a host is counted over.
+const BUDGET_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct HttpPolicy {
+    /// Attempts after the first one, for failures that may go away.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every following one.
+    pub base_backoff: Duration,
+    /// Longest delay between two attempts, a longer `Retry-After` fails the request.
+    pub max_backoff: Duration,
+    pub max_concurrent_per_host: usize,
+    /// Requests started per host and per minute, retries included.
+    pub requests_per_minute: u32,
+}
+
+impl Default for HttpPolicy {
+    fn default() -> Self {
+        HttpPolicy {
+            max_retries: 3,
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_concurrent_per_host: 6,
+            requests_per_minute: 300,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HttpError {
+    #[error("Request to {url} failed: {source}")]
+    Request {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("{url} answered {status}")]
+    Status { url: String, status: StatusCode },
+
+    #[error("{url} is rate limited, retry after {retry_after:?}")]
+    RateLimited {
+        url: String,
+        retry_after: Option<Duration>,
+    },
+
+    #[error("{url} was not requested, the site can't be reached and it isn't cached")]
+    Offline { url: String },
+}
+
+impl HttpError {
+    pub(crate) fn request(source: reqwest::Error) -> Self {
+        HttpError::Request {
+            url: source.url().map(|url| url.to_string()).unwrap_or_default(),
+            source,
+        }
+    }
+
+    /// The HTTP status the server answered with, if it answered.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            HttpError::Request { source, .. } => source.status(),
+            HttpError::Status { status, .. } => Some(*status),
+            HttpError::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            HttpError::Offline { .. } => None,
+        }
+    }
+
+    pub fn is_status(&self) -> bool {
+        self.status().is_some()
+    }
+
+    /// Whether the same request could succeed later.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            HttpError::Request { source, .. } => is_transient_error(source),
+            HttpError::Status { status, .. } => is_retryable_status(*status),
+            HttpError::RateLimited { .. } | HttpError::Offline { .. } => true,
+        }
+    }
+}
+
+impl Serialize for HttpError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+fn is_transient_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect()
+}
+
+/// `Retry-After` as a delay, it is either a number of seconds or an HTTP date.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+struct RequestBudget {
+    window_start: Instant,
+    used: u32,
+}
+
+/// The limits shared by every request to one host.
+struct HostLimiter {
+    in_flight: Semaphore,
+    budget: Mutex<RequestBudget>,
+    /// Set from a `Retry-After`, no request to the host starts before it.
+    paused_until: Mutex<Option<Instant>>,
+}
+
+impl HostLimiter {
+    fn new(policy: &HttpPolicy) -> Self {
+        HostLimiter {
+            in_flight: Semaphore::new(policy.max_concurrent_per_host.max(1)),
+            budget: Mutex::new(RequestBudget {
+                window_start: Instant::now(),
+                used: 0,
+            }),
+            paused_until: Mutex::new(None),
+        }
+    }
+
+    fn pause_for(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut paused_until = self.paused_until.lock().unwrap();
+        if paused_until.is_none_or(|current| current < until) {
+            *paused_until = Some(until);
+        }
+    }
+
+    /// Wait out a pause of the host, then for room in the request budget.
+    ///
+    /// A pause longer than `max_wait` is not waited for, what is left of it is returned.
+    async fn wait_for_turn(
+        &self,
+        requests_per_minute: u32,
+        max_wait: Duration,
+    ) -> Result<(), Duration> {
+        let paused_until = *self.paused_until.lock().unwrap();
+        if let Some(until) = paused_until {
+            let remaining = until.saturating_duration_since(Instant::now());
+            if remaining > max_wait {
+                return Err(remaining);
+            }
+            tokio::time::sleep(remaining).await;
+        }
+
+        loop {
+            let wait = {
+                let mut budget = self.budget.lock().unwrap();
+                if budget.window_start.elapsed() >= BUDGET_WINDOW {
+                    budget.window_start = Instant::now();
+                    budget.used = 0;
+                }
+                if budget.used < requests_per_minute.max(1) {
+                    budget.used += 1;
+                    return Ok(());
+                }
+                BUDGET_WINDOW.saturating_sub(budget.window_start.elapsed())
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+pub struct HttpClient {
+    client: Client,
+    policy: HttpPolicy,
+    hosts: Mutex<HashMap<String, Arc<HostLimiter>>>,
+}
+
+pub static HTTP_CLIENT: Lazy<HttpClient> =
+    Lazy::new(|| HttpClient::new(CUSTOM_DNS_CLIENT.clone(), HttpPolicy::default()));
+
+impl HttpClient {
+    pub fn new(client: Client, policy: HttpPolicy) -> Self {
+        HttpClient {
+            client,
+            policy,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn head(&self, url: &str) -> RequestBuilder {
+        self.client.head(url)
+    }
+
+    fn limiter(&self, host: &str) -> Arc<HostLimiter> {
+        self.hosts
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_insert_with(|| Arc::new(HostLimiter::new(&self.policy)))
+            .clone()
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .policy
+            .base_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.policy.max_backoff);
+        // Spread the retries of requests that failed together.
+        delay.mul_f64(rand::rng().random_range(0.75..1.25))
+    }
+
+    /// Send `request` within the limits of its host, retrying what may succeed later.
+    ///
+    /// Any answer that isn't worth a retry is returned as is, `404` included,
+    /// the caller decides what a status means.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, HttpError> {
+        let mut request = request.build().map_err(HttpError::request)?;
+        let url = request.url().to_string();
+        let host = format!(
+            "{}:{}",
+            request.url().host_str().unwrap_or_default(),
+            request.url().port_or_known_default().unwrap_or_default()
+        );
+        let limiter = self.limiter(&host);
+
+        let mut attempt = 0;
+        loop {
+            // Requests with a streamed body can't be cloned, they get a single attempt.
+            let retry_request = request.try_clone();
+
+            let turn = limiter
+                .wait_for_turn(self.policy.requests_per_minute, self.policy.max_backoff)
+                .await;
+            if let Err(remaining) = turn {
+                return Err(HttpError::RateLimited {
+                    url,
+                    retry_after: Some(remaining),
+                });
+            }
+            let result = {
+                let _permit = limiter
+                    .in_flight
+                    .acquire()
+                    .await
+                    .expect("Host semaphores are never closed");
+                self.client.execute(request).await
+            };
+
+            let (error, retry_after) = match result {
+                Ok(response) if is_retryable_status(response.status()) => {
+                    let status = response.status();
+                    let retry_after = parse_retry_after(response.headers());
+                    if let Some(delay) = retry_after {
+                        limiter.pause_for(delay);
+                    }
+
+                    let error = if status == StatusCode::TOO_MANY_REQUESTS {
+                        HttpError::RateLimited {
+                            url: url.clone(),
+                            retry_after,
+                        }
+                    } else {
+                        HttpError::Status {
+                            url: url.clone(),
+                            status,
+                        }
+                    };
+                    (error, retry_after)
+                }
+                Ok(response) => {
+                    CONNECTIVITY.report_reachable();
+                    return Ok(response);
+                }
+                Err(e) if is_transient_error(&e) => {
+                    if e.is_connect() {
+                        CONNECTIVITY.report_unreachable();
+                    }
+                    (HttpError::request(e), None)
+                }
+                Err(e) => return Err(HttpError::request(e)),
+            };
+
+            let Some(next_request) = retry_request.filter(|_| attempt < self.policy.max_retries)
+            else {
+                return Err(error);
+            };
+            let delay = match retry_after {
+                Some(delay) if delay > self.policy.max_backoff => return Err(error),
+                Some(delay) => delay,
+                None => self.backoff(attempt),
+            };
+
+            attempt += 1;
+            warn!(
+                "{}, retrying in {:?} (attempt {}/{})",
+                error, delay, attempt, self.policy.max_retries
+            );
+            tokio::time::sleep(delay).await;
+            request = next_request;
+        }
+    }
+
+    /// Whether `url` answers a `HEAD` with a success status.
+    pub async fn url_exists(&self, url: &str) -> Result<bool, HttpError> {
+        let response = self.send(self.head(url)).await?;
+        Ok(response.status().is_success())
+    }
+}
@@ -1 +1,4 @@
+pub mod cache;
+pub mod connectivity;
 pub mod dns;
+pub mod http;
@@ -1,3 +1,5 @@
 pub mod client;
 pub mod settings;
+pub use client::cache::*;
+pub use client::connectivity::*;
 pub use settings::*;
@@ -1,13 +1,14 @@
-use directories::BaseDirs;
+use once_cell::sync::Lazy;
 use serde::Serialize;
 use std::fmt;
-use std::fs;
+use tokio::sync::watch;
 use tracing::error;
 use tracing::info;
 
 use crate::client::dns::FitLauncherDnsConfig;
 
 use super::creation::{GamehubSettings, InstallationSettings, RealDebridSettings};
+use super::migration::{load_settings, save_settings};
 
 #[derive(Debug, Serialize)]
 pub struct SettingsConfigurationError {
@@ -20,6 +21,14 @@ impl fmt::Display for SettingsConfigurationError {
     }
 }
 
+impl SettingsConfigurationError {
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        SettingsConfigurationError {
+            message: message.into(),
+        }
+    }
+}
+
 impl std::error::Error for SettingsConfigurationError {}
 
 impl From<reqwest::Error> for SettingsConfigurationError {
@@ -46,224 +55,70 @@ impl From<serde_json::Error> for SettingsConfigurationError {
     }
 }
 
-#[tauri::command]
-pub fn get_installation_settings() -> InstallationSettings {
-    let base_dirs = BaseDirs::new()
-        .ok_or_else(|| error!("Failed to determine base directories"))
-        .unwrap();
-
-    let installation_file_path = base_dirs
-        .config_dir()
-        .join("com.fitlauncher.carrotrub")
-        .join("fitgirlConfig")
-        .join("settings")
-        .join("installation")
-        .join("installation.json");
+/// The gamehub settings last saved while the app runs, `None` until the first save.
+static SAVED_GAMEHUB_SETTINGS: Lazy<watch::Sender<Option<GamehubSettings>>> =
+    Lazy::new(|| watch::channel(None).0);
 
-    let file_content = fs::read_to_string(&installation_file_path)
-        .map_err(|err| {
-            error!(
-                "Error reading the file at {:?}: {:#?}",
-                installation_file_path, err
-            );
-        })
-        .unwrap_or("{}".to_string());
+/// Follow the gamehub settings saved from now on, for services that keep some of them.
+pub fn watch_gamehub_settings() -> watch::Receiver<Option<GamehubSettings>> {
+    SAVED_GAMEHUB_SETTINGS.subscribe()
+}
 
-    serde_json::from_str::<InstallationSettings>(&file_content).unwrap_or_default()
+fn save_gamehub_settings(settings: GamehubSettings) -> Result<(), SettingsConfigurationError> {
+    save_settings(&settings)?;
+    SAVED_GAMEHUB_SETTINGS.send_replace(Some(settings));
+    Ok(())
 }
 
 #[tauri::command]
-pub fn get_gamehub_settings() -> GamehubSettings {
-    let base_dirs = BaseDirs::new()
-        .ok_or_else(|| error!("Failed to determine base directories"))
-        .unwrap();
-
-    let installation_file_path = base_dirs
-        .config_dir()
-        .join("com.fitlauncher.carrotrub")
-        .join("fitgirlConfig")
-        .join("settings")
-        .join("gamehub")
-        .join("gamehub.json");
-
-    let file_content = fs::read_to_string(&installation_file_path)
-        .map_err(|err| {
-            error!(
-                "Error reading the file at {:?}: {:#?}",
-                installation_file_path, err
-            );
-        })
-        .unwrap_or("{}".to_string());
-
-    serde_json::from_str::<GamehubSettings>(&file_content).unwrap_or_default()
+pub fn get_installation_settings() -> Result<InstallationSettings, SettingsConfigurationError> {
+    load_settings()
 }
 
 #[tauri::command]
-pub fn get_dns_settings() -> FitLauncherDnsConfig {
-    let base_dirs = BaseDirs::new()
-        .ok_or_else(|| error!("Failed to determine base directories"))
-        .unwrap();
-
-    let installation_file_path = base_dirs
-        .config_dir()
-        .join("com.fitlauncher.carrotrub")
-        .join("fitgirlConfig")
-        .join("settings")
-        .join("dns")
-        .join("dns.json");
-
-    let file_content = fs::read_to_string(&installation_file_path)
-        .map_err(|err| {
-            error!(
-                "Error reading the file at {:?}: {:#?}",
-                installation_file_path, err
-            );
-        })
-        .unwrap_or("{}".to_string());
+pub fn get_gamehub_settings() -> Result<GamehubSettings, SettingsConfigurationError> {
+    load_settings()
+}
 
-    serde_json::from_str::<FitLauncherDnsConfig>(&file_content).unwrap_or_default()
+#[tauri::command]
+pub fn get_dns_settings() -> Result<FitLauncherDnsConfig, SettingsConfigurationError> {
+    load_settings()
 }
 
 #[tauri::command]
 pub fn change_installation_settings(
     settings: InstallationSettings,
 ) -> Result<(), SettingsConfigurationError> {
-    let base_dirs = BaseDirs::new().ok_or_else(|| SettingsConfigurationError {
-        message: "Failed to determine base directories".to_string(),
-    })?;
-    let installation_file_path = base_dirs
-        .config_dir()
-        .join("com.fitlauncher.carrotrub")
-        .join("fitgirlConfig")
-        .join("settings")
-        .join("installation")
-        .join("installation.json");
-
-    let settings_json_string =
-        serde_json::to_string_pretty(&settings).map_err(SettingsConfigurationError::from)?;
-
-    fs::write(installation_file_path, settings_json_string)
-        .map_err(SettingsConfigurationError::from)?;
-    Ok(())
+    save_settings(&settings)
 }
 
 #[tauri::command]
 pub fn change_gamehub_settings(
     settings: GamehubSettings,
 ) -> Result<(), SettingsConfigurationError> {
-    let base_dirs = BaseDirs::new().ok_or_else(|| SettingsConfigurationError {
-        message: "Failed to determine base directories".to_string(),
-    })?;
-    let installation_file_path = base_dirs
-        .config_dir()
-        .join("com.fitlauncher.carrotrub")
-        .join("fitgirlConfig")
-        .join("settings")
-        .join("gamehub")
-        .join("gamehub.json");
-
-    let settings_json_string =
-        serde_json::to_string_pretty(&settings).map_err(SettingsConfigurationError::from)?;
-
-    fs::write(installation_file_path, settings_json_string)
-        .map_err(SettingsConfigurationError::from)?;
-    Ok(())
+    save_gamehub_settings(settings)
 }
 
 #[tauri::command]
 pub fn change_dns_settings(
     settings: FitLauncherDnsConfig,
 ) -> Result<(), SettingsConfigurationError> {
-    let base_dirs = BaseDirs::new().ok_or_else(|| SettingsConfigurationError {
-        message: "Failed to determine base directories".to_string(),
-    })?;
-    let installation_file_path = base_dirs
-        .config_dir()
-        .join("com.fitlauncher.carrotrub")
-        .join("fitgirlConfig")
-        .join("settings")
-        .join("dns")
-        .join("dns.json");
-
-    let settings_json_string =
-        serde_json::to_string_pretty(&settings).map_err(SettingsConfigurationError::from)?;
-
-    fs::write(installation_file_path, settings_json_string)
-        .map_err(SettingsConfigurationError::from)?;
-    Ok(())
+    save_settings(&settings)
 }
 
 #[tauri::command]
 pub fn reset_installation_settings() -> Result<(), SettingsConfigurationError> {
-    let base_dirs = BaseDirs::new().ok_or_else(|| SettingsConfigurationError {
-        message: "Failed to determine base directories".to_string(),
-    })?;
-    let installation_file_path = base_dirs
-        .config_dir()
-        .join("com.fitlauncher.carrotrub")
-        .join("fitgirlConfig")
-        .join("settings")
-        .join("installation")
-        .join("installation.json");
-
-    let settings_json_string = serde_json::to_string_pretty(&InstallationSettings::default())
-        .map_err(SettingsConfigurationError::from)?;
-
-    fs::write(installation_file_path, settings_json_string)
-        .map_err(SettingsConfigurationError::from)?;
-    Ok(())
+    save_settings(&InstallationSettings::default())
 }
 
 #[tauri::command]
 pub fn reset_gamehub_settings() -> Result<(), SettingsConfigurationError> {
-    let base_dirs = BaseDirs::new().ok_or_else(|| SettingsConfigurationError {
-        message: "Failed to determine base directories".to_string(),
-    })?;
-    let gamehub_file_path = base_dirs
-        .config_dir()
-        .join("com.fitlauncher.carrotrub")
-        .join("fitgirlConfig")
-        .join("settings")
-        .join("gamehub")
-        .join("gamehub.json");
-
-    let settings_json_string = serde_json::to_string_pretty(&GamehubSettings::default())
-        .map_err(SettingsConfigurationError::from)?;
-
-    fs::write(gamehub_file_path, settings_json_string).map_err(SettingsConfigurationError::from)?;
-    Ok(())
+    save_gamehub_settings(GamehubSettings::default())
 }
 
 #[tauri::command]
 pub fn reset_dns_settings() -> Result<(), SettingsConfigurationError> {
-    let base_dirs = BaseDirs::new().ok_or_else(|| SettingsConfigurationError {
-        message: "Failed to determine base directories".to_string(),
-    })?;
-    let dns_file_path = base_dirs
-        .config_dir()
-        .join("com.fitlauncher.carrotrub")
-        .join("fitgirlConfig")
-        .join("settings")
-        .join("dns")
-        .join("dns.json");
-
-    let settings_json_string = serde_json::to_string_pretty(&FitLauncherDnsConfig::default())
-        .map_err(SettingsConfigurationError::from)?;
-
-    fs::write(dns_file_path, settings_json_string).map_err(SettingsConfigurationError::from)?;
-    Ok(())
-}
-
-#[tauri::command]
-pub async fn clear_all_cache() -> Result<(), SettingsConfigurationError> {
-    let image_cache_path = directories::BaseDirs::new()
-        .expect("Could not determine base directories")
-        .config_local_dir() // Points to AppData\Local (or equivalent on other platforms)
-        .join("com.fitlauncher.carrotrub")
-        .join("image_cache.json");
-
-    tokio::fs::remove_file(image_cache_path).await?;
-    Ok(())
+    save_settings(&FitLauncherDnsConfig::default())
 }
 
 #[tauri::command]
@@ -304,54 +159,17 @@ pub fn open_logs_directory() -> Result<(), String> {
         }
     }
 
-        Ok(())
-    }
-    
-    #[tauri::command]
-    pub fn get_realdebrid_settings() -> RealDebridSettings {
-        let base_dirs = BaseDirs::new()
-            .ok_or_else(|| error!("Failed to determine base directories"))
-            .unwrap();
-    
-        let rd_file_path = base_dirs
-            .config_dir()
-            .join("com.fitlauncher.carrotrub")
-            .join("fitgirlConfig")
-            .join("settings")
-            .join("realdebrid")
-            .join("realdebrid.json");
-    
-        let file_content = fs::read_to_string(&rd_file_path)
-            .map_err(|err| {
-                error!(
-                    "Error reading the file at {:?}: {:#?}",
-                    rd_file_path, err
-                );
-            })
-            .unwrap_or("{}".to_string());
-    
-        serde_json::from_str::<RealDebridSettings>(&file_content).unwrap_or_default()
-    }
-    
-    #[tauri::command]
-    pub fn change_realdebrid_settings(
-        settings: RealDebridSettings,
-    ) -> Result<(), SettingsConfigurationError> {
-        let base_dirs = BaseDirs::new().ok_or_else(|| SettingsConfigurationError {
-            message: "Failed to determine base directories".to_string(),
-        })?;
-        let rd_file_path = base_dirs
-            .config_dir()
-            .join("com.fitlauncher.carrotrub")
-            .join("fitgirlConfig")
-            .join("settings")
-            .join("realdebrid")
-            .join("realdebrid.json");
-    
-        let settings_json_string =
-            serde_json::to_string_pretty(&settings).map_err(SettingsConfigurationError::from)?;
-    
-        fs::write(rd_file_path, settings_json_string)
-            .map_err(SettingsConfigurationError::from)?;
-        Ok(())
-    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_realdebrid_settings() -> Result<RealDebridSettings, SettingsConfigurationError> {
+    load_settings()
+}
+
+#[tauri::command]
+pub fn change_realdebrid_settings(
+    settings: RealDebridSettings,
+) -> Result<(), SettingsConfigurationError> {
+    save_settings(&settings)
+}
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::{fs, io::Write};
 use tracing::error;
 
+use super::migration::versioned_json;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InstallationSettings {
     pub auto_clean: bool,
@@ -28,6 +30,17 @@ impl Default for InstallationSettings {
 pub struct GamehubSettings {
     nsfw_censorship: bool,
     auto_get_colors_popular_games: bool,
+    #[serde(default)]
+    pub feed_refresh: FeedRefreshSettings,
+    #[serde(default)]
+    pub popular_period: PopularPeriod,
+    /// How many games of the popular list are scraped and shown.
+    #[serde(default = "default_popular_games_count")]
+    pub popular_games_count: usize,
+    #[serde(default)]
+    pub source: SourceSettings,
+    #[serde(default)]
+    pub image_cache: ImageCacheSettings,
 }
 
 impl Default for GamehubSettings {
@@ -35,6 +48,97 @@ impl Default for GamehubSettings {
         GamehubSettings {
             nsfw_censorship: true,
             auto_get_colors_popular_games: false,
+            feed_refresh: FeedRefreshSettings::default(),
+            popular_period: PopularPeriod::default(),
+            popular_games_count: default_popular_games_count(),
+            source: SourceSettings::default(),
+            image_cache: ImageCacheSettings::default(),
+        }
+    }
+}
+
+fn default_popular_games_count() -> usize {
+    8
+}
+
+/// Which popular repacks list the hub shows.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PopularPeriod {
+    #[default]
+    Month,
+    Year,
+}
+
+/// Where the repacks are scraped from. Paths are relative to `base_url`,
+/// so a moved domain or a local fixture server only needs `base_url` changed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SourceSettings {
+    pub base_url: String,
+    /// Listing of the newest repacks, followed by `/page/<n>`.
+    pub new_repacks_path: String,
+    pub updates_digest_path: String,
+    pub popular_month_path: String,
+    pub popular_year_path: String,
+    pub sitemap_index_path: String,
+}
+
+impl Default for SourceSettings {
+    fn default() -> Self {
+        SourceSettings {
+            base_url: "https://fitgirl-repacks.site".to_string(),
+            new_repacks_path: "category/lossless-repack".to_string(),
+            updates_digest_path: "category/updates-digest".to_string(),
+            popular_month_path: "popular-repacks".to_string(),
+            popular_year_path: "popular-repacks-of-the-year".to_string(),
+            sitemap_index_path: "sitemap_index.xml".to_string(),
+        }
+    }
+}
+
+/// Limits of the image caches.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ImageCacheSettings {
+    /// Games whose screenshot links are remembered, the least recently viewed are forgotten.
+    pub games_capacity: usize,
+    /// Disk space of the stored pictures and their thumbnails, in megabytes.
+    pub store_max_megabytes: u64,
+}
+
+impl Default for ImageCacheSettings {
+    fn default() -> Self {
+        ImageCacheSettings {
+            games_capacity: 30,
+            store_max_megabytes: 512,
+        }
+    }
+}
+
+/// How often each feed is scraped again while the app is running, in minutes.
+/// `0` disables the background refresh of that feed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct FeedRefreshSettings {
+    pub newly_added_minutes: u64,
+    pub popular_minutes: u64,
+    pub recently_updated_minutes: u64,
+    pub discovery_minutes: u64,
+    pub sitemaps_minutes: u64,
+    /// Random spread applied to every interval, in percent of it.
+    pub jitter_percent: u8,
+}
+
+impl Default for FeedRefreshSettings {
+    fn default() -> Self {
+        FeedRefreshSettings {
+            newly_added_minutes: 30,
+            popular_minutes: 120,
+            recently_updated_minutes: 60,
+            discovery_minutes: 180,
+            sitemaps_minutes: 720,
+            jitter_percent: 10,
         }
     }
 }
@@ -56,7 +160,7 @@ pub fn create_installation_settings_file() -> Result<(), std::io::Error> {
     let installation_file_path = installation_folder_path.join("installation.json");
     let default_config = InstallationSettings::default();
 
-    let default_config_data = serde_json::to_string_pretty(&default_config).map_err(|err| {
+    let default_config_data = versioned_json(&default_config).map_err(|err| {
         error!(
             "Failed to serialize default Installation config: {:#?}",
             err
@@ -147,7 +251,7 @@ pub fn create_gamehub_settings_file() -> Result<(), std::io::Error> {
     let installation_file_path = installation_folder_path.join("gamehub.json");
     let default_config = GamehubSettings::default();
 
-    let default_config_data = serde_json::to_string_pretty(&default_config).map_err(|err| {
+    let default_config_data = versioned_json(&default_config).map_err(|err| {
         error!("Failed to serialize default Gamehub config: {:#?}", err);
         std::io::Error::new(
             std::io::ErrorKind::InvalidData,
@@ -176,25 +280,6 @@ pub fn create_gamehub_settings_file() -> Result<(), std::io::Error> {
     Ok(())
 }
 
-pub fn create_image_cache_file() -> Result<(), std::io::Error> {
-    let base_dirs = BaseDirs::new().expect("Failed to determine base directories");
-    let image_cache_file_path = base_dirs
-        .config_dir()
-        .join("com.fitlauncher.carrotrub")
-        .join("image_cache.json");
-
-    if !image_cache_file_path.exists() {
-        fs::File::create(&image_cache_file_path).map_err(|err| {
-            error!("Error creating the file: {:#?}", err);
-            std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Directory not found for creating installation file",
-            )
-        })?;
-    }
-    Ok(())
-}
-
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RealDebridSettings {
     pub client_id: String,
@@ -227,7 +312,7 @@ pub fn create_realdebrid_settings_file() -> Result<(), std::io::Error> {
     let rd_file_path = rd_folder_path.join("realdebrid.json");
     if !rd_file_path.exists() {
         let default_config = RealDebridSettings::default();
-        let default_config_data = serde_json::to_string_pretty(&default_config).map_err(|err| {
+        let default_config_data = versioned_json(&default_config).map_err(|err| {
             error!("Failed to serialize default RealDebrid config: {:#?}", err);
             std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
@@ -0,0 +1,244 @@
+//! Schema versions of the settings files and their upgrades.
+//!
+//! Every file under `fitgirlConfig/settings` carries a `schema_version`. When a
+//! settings struct changes in a way `#[serde(default)]` can't absorb (a renamed
+//! field, a changed type...), push a [`Migration`] to its `MIGRATIONS`, the
+//! schema version follows from their count.
+//!
+//! Loading a file never resets it: fields that can't be read anymore fall back
+//! to their default one by one, the previous file is backed up before it is
+//! rewritten, and a file that isn't a settings object at all is reported and
+//! left untouched. Settings are read from many scraper threads at once, so
+//! every read and write of a settings file goes through one process-wide lock.
+
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use directories::BaseDirs;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use tempfile::NamedTempFile;
+use tracing::{error, info, warn};
+
+use crate::client::dns::FitLauncherDnsConfig;
+
+use super::config::SettingsConfigurationError;
+use super::creation::{GamehubSettings, InstallationSettings, RealDebridSettings};
+
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Upgrade the fields of a file from one schema version to the next.
+pub type Migration = fn(&mut Map<String, Value>);
+
+/// Held while a settings file is read, upgraded or written.
+static SETTINGS_FILES: Mutex<()> = Mutex::new(());
+
+fn lock_settings_files() -> MutexGuard<'static, ()> {
+    SETTINGS_FILES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub trait VersionedSettings: Serialize + DeserializeOwned + Default {
+    /// Folder under `fitgirlConfig/settings`, and the file name in it.
+    const LOCATION: (&'static str, &'static str);
+
+    /// `MIGRATIONS[i]` upgrades a file of schema `i + 1` to schema `i + 2`.
+    ///
+    /// Files written before schemas were versioned count as schema 1.
+    const MIGRATIONS: &'static [Migration] = &[];
+
+    fn schema_version() -> u64 {
+        Self::MIGRATIONS.len() as u64 + 1
+    }
+
+    fn file_path() -> Result<PathBuf, SettingsConfigurationError> {
+        let base_dirs = BaseDirs::new().ok_or_else(|| {
+            SettingsConfigurationError::new("Failed to determine base directories")
+        })?;
+        let (folder, file_name) = Self::LOCATION;
+
+        Ok(base_dirs
+            .config_dir()
+            .join("com.fitlauncher.carrotrub")
+            .join("fitgirlConfig")
+            .join("settings")
+            .join(folder)
+            .join(file_name))
+    }
+}
+
+impl VersionedSettings for InstallationSettings {
+    const LOCATION: (&'static str, &'static str) = ("installation", "installation.json");
+}
+
+impl VersionedSettings for GamehubSettings {
+    const LOCATION: (&'static str, &'static str) = ("gamehub", "gamehub.json");
+}
+
+impl VersionedSettings for FitLauncherDnsConfig {
+    const LOCATION: (&'static str, &'static str) = ("dns", "dns.json");
+}
+
+impl VersionedSettings for RealDebridSettings {
+    const LOCATION: (&'static str, &'static str) = ("realdebrid", "realdebrid.json");
+}
+
+fn unrecoverable(path: &Path, reason: impl std::fmt::Display) -> SettingsConfigurationError {
+    let error = SettingsConfigurationError::new(format!(
+        "{} can't be read and was left untouched: {}",
+        path.display(),
+        reason
+    ));
+    error!("{}", error);
+    error
+}
+
+/// `settings` as pretty JSON, stamped with its schema version.
+pub fn versioned_json<T: VersionedSettings>(settings: &T) -> Result<String, serde_json::Error> {
+    let mut value = serde_json::to_value(settings)?;
+    if let Value::Object(fields) = &mut value {
+        fields.insert(SCHEMA_VERSION_KEY.to_string(), T::schema_version().into());
+    }
+    serde_json::to_string_pretty(&value)
+}
+
+/// Write `settings` to its file, through a temporary file so a crash can't truncate it.
+pub fn save_settings<T: VersionedSettings>(settings: &T) -> Result<(), SettingsConfigurationError> {
+    let path = T::file_path()?;
+    let _settings_files = lock_settings_files();
+    write_settings(&path, settings)
+}
+
+/// Every write goes through its own temp file, a concurrent one can't move it half-written.
+fn write_settings<T: VersionedSettings>(
+    path: &Path,
+    settings: &T,
+) -> Result<(), SettingsConfigurationError> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+
+    let mut temp_file = NamedTempFile::new_in(parent)?;
+    temp_file.write_all(versioned_json(settings)?.as_bytes())?;
+    temp_file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Read the settings file of `T`, upgrading it to the current schema if needed.
+///
+/// A missing file gives the defaults.
+pub fn load_settings<T: VersionedSettings>() -> Result<T, SettingsConfigurationError> {
+    load_settings_from(&T::file_path()?)
+}
+
+/// [`load_settings`] from the file at `path`.
+pub fn load_settings_from<T: VersionedSettings>(
+    path: &Path,
+) -> Result<T, SettingsConfigurationError> {
+    // The upgrade below backs up and rewrites the file, only one thread may do it.
+    let _settings_files = lock_settings_files();
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(T::default()),
+        Err(e) => return Err(unrecoverable(path, e)),
+    };
+    if content.trim().is_empty() {
+        return Ok(T::default());
+    }
+
+    let mut fields = match serde_json::from_str::<Value>(&content) {
+        Ok(Value::Object(fields)) => fields,
+        Ok(_) => return Err(unrecoverable(path, "not a settings object")),
+        Err(e) => return Err(unrecoverable(path, e)),
+    };
+
+    let file_version = match fields.remove(SCHEMA_VERSION_KEY) {
+        None => None,
+        Some(version) => match version.as_u64() {
+            Some(version) if version >= 1 => Some(version),
+            _ => {
+                return Err(unrecoverable(
+                    path,
+                    format!("invalid schema version {}", version),
+                ));
+            }
+        },
+    };
+    let version = file_version.unwrap_or(1);
+
+    if version > T::schema_version() {
+        return Err(unrecoverable(
+            path,
+            format!(
+                "written with schema {} by a newer FitLauncher, this one knows schema {}",
+                version,
+                T::schema_version()
+            ),
+        ));
+    }
+
+    for migration in &T::MIGRATIONS[(version - 1) as usize..] {
+        migration(&mut fields);
+    }
+
+    let (settings, dropped_fields) =
+        match serde_json::from_value::<T>(Value::Object(fields.clone())) {
+            Ok(settings) => (settings, Vec::new()),
+            Err(_) => recover_fields::<T>(fields).map_err(|e| unrecoverable(path, e))?,
+        };
+
+    let up_to_date = file_version == Some(T::schema_version()) && dropped_fields.is_empty();
+    if !up_to_date {
+        if !dropped_fields.is_empty() {
+            warn!(
+                "Fields {:?} of {} were unreadable and reset to their default",
+                dropped_fields,
+                path.display()
+            );
+        }
+
+        let backup_path = path.with_extension(format!("v{}.bak.json", version));
+        fs::copy(path, &backup_path)?;
+        write_settings(path, &settings)?;
+        info!(
+            "Upgraded {} to schema {}, the previous file is kept at {}",
+            path.display(),
+            T::schema_version(),
+            backup_path.display()
+        );
+    }
+
+    Ok(settings)
+}
+
+/// Rebuild `T` from the defaults, taking every field of the file that still reads.
+///
+/// Returns the names of the fields that had to be left at their default.
+fn recover_fields<T: VersionedSettings>(
+    fields: Map<String, Value>,
+) -> Result<(T, Vec<String>), serde_json::Error> {
+    let mut merged = match serde_json::to_value(T::default())? {
+        Value::Object(defaults) => defaults,
+        _ => Map::new(),
+    };
+    let mut dropped_fields = Vec::new();
+
+    for (key, value) in fields {
+        let previous = merged.insert(key.clone(), value);
+        if serde_json::from_value::<T>(Value::Object(merged.clone())).is_err() {
+            match previous {
+                Some(previous) => merged.insert(key.clone(), previous),
+                None => merged.remove(&key),
+            };
+            dropped_fields.push(key);
+        }
+    }
+
+    Ok((
+        serde_json::from_value(Value::Object(merged))?,
+        dropped_fields,
+    ))
+}
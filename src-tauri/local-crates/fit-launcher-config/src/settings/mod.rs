@@ -1,3 +1,4 @@
 pub mod config;
 pub mod creation;
+pub mod migration;
 pub use config::*;
@@ -0,0 +1,49 @@
+use fit_launcher_config::settings::creation::GamehubSettings;
+use fit_launcher_config::settings::migration::{SCHEMA_VERSION_KEY, load_settings_from};
+use serde_json::Value;
+
+fn read_json(path: &std::path::Path) -> Value {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn upgrades_an_unversioned_gamehub_file() {
+    let fixture = format!(
+        "{}/tests/fixtures/gamehub_v1.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("gamehub.json");
+    std::fs::copy(&fixture, &path).unwrap();
+
+    let settings: GamehubSettings = load_settings_from(&path).unwrap();
+
+    // The user's values are kept, only the missing ones get their default.
+    let settings = serde_json::to_value(settings).unwrap();
+    assert_eq!(settings["nsfw_censorship"], false);
+    assert_eq!(settings["auto_get_colors_popular_games"], true);
+    assert_eq!(settings["popular_period"], "year");
+    assert_eq!(settings["feed_refresh"]["newly_added_minutes"], 15);
+    assert_eq!(settings["feed_refresh"]["popular_minutes"], 0);
+    assert_eq!(settings["popular_games_count"], 8);
+
+    // The file is rewritten with every field and the current schema.
+    let upgraded = read_json(&path);
+    assert_eq!(upgraded[SCHEMA_VERSION_KEY], 1);
+    assert_eq!(upgraded["nsfw_censorship"], false);
+    assert_eq!(upgraded["feed_refresh"]["newly_added_minutes"], 15);
+    assert_eq!(upgraded["image_cache"]["games_capacity"], 30);
+    assert!(upgraded["source"]["base_url"].is_string());
+
+    // The original is backed up as it was.
+    let backup = directory.path().join("gamehub.v1.bak.json");
+    assert_eq!(
+        read_json(&backup),
+        read_json(std::path::Path::new(&fixture))
+    );
+
+    // Loading an up-to-date file leaves it alone.
+    std::fs::remove_file(&backup).unwrap();
+    let _: GamehubSettings = load_settings_from(&path).unwrap();
+    assert!(!backup.exists());
+}
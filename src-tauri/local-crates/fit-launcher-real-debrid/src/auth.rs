@@ -21,7 +21,7 @@ pub struct AuthState {
 
 impl AuthState {
     pub fn new() -> Self {
-        let settings = get_realdebrid_settings();
+        let settings = get_realdebrid_settings().unwrap_or_default();
         Self {
             access_token: Arc::new(Mutex::new(None)),
             refresh_token: Arc::new(Mutex::new(settings.refresh_token)),
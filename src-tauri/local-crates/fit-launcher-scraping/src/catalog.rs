@@ -0,0 +1,256 @@
+//! Local catalog of every post of the site, built from the post sitemaps.
+//!
+//! The sitemap index is discovered at runtime and only the sitemaps whose
+//! `lastmod` moved since the last refresh are downloaded again. A post whose
+//! own `lastmod` moved loses its cached page, it is scraped again next time
+//! it is opened.
+
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use chrono::{DateTime, FixedOffset};
+use fit_launcher_config::client::cache::{HTTP_CACHE, cached_get};
+use futures::{StreamExt, stream};
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    errors::ScrapingError, parser::game_id, source::repack_source, store::write_json_atomic,
+    structs::CatalogEntry,
+};
+
+/// Set once the sitemaps of older versions have been looked for in this run.
+static LEGACY_SITEMAPS_CHECKED: AtomicBool = AtomicBool::new(false);
+
+/// A `<sitemap>` entry of the sitemap index.
+#[derive(Debug, Clone)]
+pub struct SitemapRef {
+    pub loc: String,
+    pub lastmod: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CatalogRefresh {
+    pub sitemaps_fetched: usize,
+    pub added: usize,
+    pub updated: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Catalog {
+    /// `lastmod` of every post sitemap, as of its last successful download.
+    pub sitemaps: BTreeMap<String, Option<DateTime<FixedOffset>>>,
+    /// Every known post, keyed by URL.
+    pub entries: BTreeMap<String, CatalogEntry>,
+}
+
+impl Catalog {
+    /// Load the catalog, a missing or unreadable file gives an empty catalog.
+    pub async fn load(path: &Path) -> Catalog {
+        let Ok(content) = tokio::fs::read_to_string(path).await else {
+            return Catalog::default();
+        };
+
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Catalog at {:?} is corrupted, rebuilding it: {}", path, e);
+            Catalog::default()
+        })
+    }
+
+    /// Write the catalog through a temp file so a crash never leaves it half-written.
+    pub fn save(&self, path: &Path) -> Result<(), ScrapingError> {
+        write_json_atomic(path, self)
+    }
+
+    /// Merge the posts of one sitemap, returns how many were added and the URLs
+    /// of the posts that changed.
+    fn merge_sitemap(
+        &mut self,
+        sitemap: SitemapRef,
+        posts: Vec<CatalogEntry>,
+    ) -> (usize, Vec<String>) {
+        let mut added = 0;
+        let mut updated = Vec::new();
+
+        for post in posts {
+            match self.entries.get_mut(&post.url) {
+                Some(existing) if existing.lastmod != post.lastmod => {
+                    existing.lastmod = post.lastmod;
+                    existing.image = post.image.or(existing.image.take());
+                    updated.push(post.url);
+                }
+                Some(_) => {}
+                None => {
+                    self.entries.insert(post.url.clone(), post);
+                    added += 1;
+                }
+            }
+        }
+
+        self.sitemaps.insert(sitemap.loc, sitemap.lastmod);
+        (added, updated)
+    }
+}
+
+/// Parse the sitemap index, only the post sitemaps are kept.
+pub fn parse_sitemap_index(xml: &str) -> Vec<SitemapRef> {
+    let document = Html::parse_document(xml);
+    let sitemap_selector = Selector::parse("sitemap").unwrap();
+
+    document
+        .select(&sitemap_selector)
+        .filter_map(|sitemap| {
+            let loc = child_text(sitemap, "loc")?;
+            loc.contains("post-sitemap").then(|| SitemapRef {
+                lastmod: child_text(sitemap, "lastmod").and_then(parse_lastmod),
+                loc,
+            })
+        })
+        .collect()
+}
+
+/// Parse a post sitemap (`<urlset>`) into catalog entries.
+pub fn parse_post_sitemap(xml: &str) -> Vec<CatalogEntry> {
+    let document = Html::parse_document(xml);
+    let url_selector = Selector::parse("url").unwrap();
+    let image_selector = Selector::parse(r"image\:loc").unwrap();
+
+    document
+        .select(&url_selector)
+        .filter_map(|url_elem| {
+            let url = child_text(url_elem, "loc")?;
+            let slug = game_id(&url);
+
+            // The sitemap also lists the home page, which has no slug.
+            if slug.is_empty() || url.trim_end_matches('/').ends_with(".site") {
+                return None;
+            }
+
+            Some(CatalogEntry {
+                title: title_from_slug(&slug),
+                lastmod: child_text(url_elem, "lastmod").and_then(parse_lastmod),
+                image: url_elem
+                    .select(&image_selector)
+                    .next()
+                    .map(|e| e.text().collect::<String>().trim().to_string()),
+                url,
+                slug,
+            })
+        })
+        .collect()
+}
+
+/// `cyberpunk-2077-ultimate-edition` -> `Cyberpunk 2077 Ultimate Edition`
+pub fn title_from_slug(slug: &str) -> String {
+    slug.split('-')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn child_text(parent: ElementRef, name: &str) -> Option<String> {
+    parent
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|child| child.value().name() == name)
+        .map(|child| child.text().collect::<String>().trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn parse_lastmod(lastmod: String) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(&lastmod).ok()
+}
+
+async fn fetch_xml(url: &str) -> Result<String, ScrapingError> {
+    Ok(cached_get(url).await?.body)
+}
+
+/// Remove the `post-sitemap*.xml` files older versions downloaded next to the catalog.
+async fn remove_legacy_sitemaps(directory: &Path) {
+    let Ok(mut files) = tokio::fs::read_dir(directory).await else {
+        return;
+    };
+
+    while let Ok(Some(file)) = files.next_entry().await {
+        let file_name = file.file_name();
+        let file_name = file_name.to_string_lossy();
+        if !(file_name.starts_with("post-sitemap") && file_name.ends_with(".xml")) {
+            continue;
+        }
+
+        if let Err(e) = tokio::fs::remove_file(file.path()).await {
+            warn!("Failed to remove the old sitemap {}: {}", file_name, e);
+        }
+    }
+}
+
+/// Bring the catalog at `catalog_path` up to date with the site sitemaps.
+pub async fn refresh_catalog(catalog_path: &Path) -> Result<CatalogRefresh, ScrapingError> {
+    let mut catalog = Catalog::load(catalog_path).await;
+    if !LEGACY_SITEMAPS_CHECKED.swap(true, Ordering::Relaxed)
+        && let Some(directory) = catalog_path.parent()
+    {
+        remove_legacy_sitemaps(directory).await;
+    }
+    let sitemap_index_url = repack_source().sitemap_index_url();
+    let sitemaps = parse_sitemap_index(&fetch_xml(&sitemap_index_url).await?);
+
+    let outdated: Vec<SitemapRef> = sitemaps
+        .into_iter()
+        .filter(|sitemap| {
+            sitemap.lastmod.is_none()
+                || catalog.sitemaps.get(&sitemap.loc) != Some(&sitemap.lastmod)
+        })
+        .collect();
+
+    let fetched: Vec<(SitemapRef, Result<String, ScrapingError>)> = stream::iter(outdated)
+        .map(|sitemap| async move {
+            let body = fetch_xml(&sitemap.loc).await;
+            (sitemap, body)
+        })
+        .buffer_unordered(3)
+        .collect()
+        .await;
+
+    let mut refresh = CatalogRefresh::default();
+    for (sitemap, body) in fetched {
+        match body {
+            Ok(body) => {
+                // A failed sitemap keeps its old lastmod so it is retried next time.
+                let (added, updated) = catalog.merge_sitemap(sitemap, parse_post_sitemap(&body));
+                refresh.sitemaps_fetched += 1;
+                refresh.added += added;
+                refresh.updated += updated.len();
+
+                for url in updated {
+                    if let Err(e) = HTTP_CACHE.remove(&url).await {
+                        warn!("Failed to drop the cached page of {}: {}", url, e);
+                    }
+                }
+            }
+            Err(e) => error!("Failed to download sitemap {}: {}", sitemap.loc, e),
+        }
+    }
+
+    refresh.total = catalog.entries.len();
+    catalog.save(catalog_path)?;
+
+    info!(
+        "Catalog refreshed: {} sitemaps fetched, {} posts added, {} updated, {} total",
+        refresh.sitemaps_fetched, refresh.added, refresh.updated, refresh.total
+    );
+
+    Ok(refresh)
+}
@@ -1,3 +1,4 @@
+use fit_launcher_config::client::http::HttpError;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -7,6 +8,10 @@ pub enum ScrapingError {
     #[serde(skip)]
     ReqwestError(#[from] reqwest::Error),
 
+    #[error("HTTP Error: {0}")]
+    #[serde(skip)]
+    HttpError(#[from] HttpError),
+
     #[error("Selector Parsing Error: {0}")]
     SelectorError(String),
 
@@ -23,6 +28,10 @@ pub enum ScrapingError {
     #[error("Global Error: {0}")]
     #[serde(skip)]
     GlobalError(String),
+
+    #[error("Game Store Error: {0}")]
+    #[serde(skip)]
+    DatabaseError(#[from] rusqlite::Error),
 }
 
 #[derive(Debug, Serialize)]
@@ -46,6 +55,14 @@ impl From<reqwest::Error> for SingularFetchError {
     }
 }
 
+impl From<HttpError> for SingularFetchError {
+    fn from(error: HttpError) -> Self {
+        SingularFetchError {
+            message: error.to_string(),
+        }
+    }
+}
+
 impl From<std::io::Error> for SingularFetchError {
     fn from(error: std::io::Error) -> Self {
         SingularFetchError {
@@ -54,6 +71,14 @@ impl From<std::io::Error> for SingularFetchError {
     }
 }
 
+impl From<ScrapingError> for SingularFetchError {
+    fn from(error: ScrapingError) -> Self {
+        SingularFetchError {
+            message: error.to_string(),
+        }
+    }
+}
+
 impl From<serde_json::Error> for SingularFetchError {
     fn from(error: serde_json::Error) -> Self {
         SingularFetchError {
@@ -61,3 +86,33 @@ impl From<serde_json::Error> for SingularFetchError {
         }
     }
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum PasteError {
+    #[error("Paste Decryption Error: {0}")]
+    Decrypt(#[from] fitgirl_decrypt::Error),
+
+    #[error("Paste Request Error: {0}")]
+    Http(#[from] HttpError),
+
+    #[error("Malformed Paste Response: {0}")]
+    MalformedResponse(#[from] serde_json::Error),
+
+    #[error("Malformed Paste Attachment: {0}")]
+    MalformedAttachment(String),
+
+    #[error("Saving Torrent File Error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Tauri Error: {0}")]
+    Tauri(#[from] tauri::Error),
+}
+
+impl Serialize for PasteError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
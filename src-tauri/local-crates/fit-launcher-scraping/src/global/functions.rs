@@ -1,211 +1,99 @@
 use anyhow::Result;
 use core::str;
-use fit_launcher_config::client::dns::CUSTOM_DNS_CLIENT;
-use futures::{StreamExt, stream::FuturesOrdered};
-use reqwest::header::RANGE;
-use std::time::Instant;
+use fit_launcher_config::client::cache::{CachedResponse, cached_get};
+use fit_launcher_config::settings::{config::get_gamehub_settings, creation::PopularPeriod};
+use futures::{
+    StreamExt,
+    stream::{self, FuturesOrdered},
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Instant,
+};
 use tauri::{Emitter, Manager};
-use tokio::io::AsyncWriteExt;
 use tracing::{error, info};
 
-use crate::{errors::ScrapingError, structs::Game};
-
-pub async fn download_sitemap(
-    app_handle: tauri::AppHandle,
-    url: &str,
-    filename: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut response = CUSTOM_DNS_CLIENT.get(url).send().await?;
-
-    let mut binding = app_handle.path().app_data_dir().unwrap();
-    binding.push("sitemaps");
-
-    let file_path = binding.join(format!("{}.xml", filename));
-
-    let mut file = tokio::fs::File::create(&file_path).await?;
-    while let Some(chunk) = response.chunk().await? {
-        file.write_all(&chunk).await?;
-    }
-
-    Ok(())
-}
-
-/// Helper function.
-async fn check_url_status(url: &str) -> bool {
-    let response = CUSTOM_DNS_CLIENT
-        .head(url)
-        .header(RANGE, "bytes=0-8")
-        .send()
-        .await
-        .unwrap();
-    response.status().is_success()
-}
+use crate::{
+    errors::ScrapingError,
+    health::{PostField, ScrapeReport, record_report},
+    parser::{REPACK_POST_PARSER, game_id},
+    source::{RepackSource, repack_source},
+    store::{GAME_STORE, StoredFeed, write_json_atomic},
+    structs::{Game, RepackPost, UpdateEntry, dedupe_by_game_id},
+    variants::resolve_variant,
+};
 
 #[tokio::main]
-pub async fn scraping_func(app_handle: tauri::AppHandle) -> Result<(), Box<ScrapingError>> {
-    use tokio::fs;
-
+pub async fn scraping_func() -> Result<(), Box<ScrapingError>> {
     let start_time = Instant::now();
     let mut recently_up_games: Vec<Game> = Vec::new();
+    let source = repack_source();
 
     // Fetch tasks for pages concurrently
     let fetch_tasks: FuturesOrdered<_> = (1..=2)
-        .map(|page_number| async move {
-            let url = format!(
-                "https://fitgirl-repacks.site/category/lossless-repack/page/{}",
-                page_number
-            );
-
-            let res = CUSTOM_DNS_CLIENT.get(&url).send().await.map_err(|e| {
-                eprintln!("Failed to get a response from URL: {}", &url);
-                ScrapingError::ReqwestError(e)
-            }).unwrap();
-
-            if !res.status().is_success() {
-                eprintln!(
-                    "Error: Failed to connect to the website or the website is down. Status is : {:#?}",
-                    res.status()
-                );
-                return Err(format!("Failed to fetch page {}", page_number));
+        .map(|page_number| {
+            let url = source.listing_page_url(page_number);
+            async move {
+                cached_get(&url).await.map_err(|e| {
+                    eprintln!("Failed to get a response from URL: {}", &url);
+                    format!("Failed to fetch page {}: {}", page_number, e)
+                })
             }
-
-            let body = res.text().await.map_err(|e| {
-                eprintln!("Failed to get a body from URL: {}", &url);
-                ScrapingError::ReqwestError(e)
-            }).unwrap();
-
-            let document = scraper::Html::parse_document(&body);
-            let article_selector = scraper::Selector::parse("article")
-                .map_err(|err| ScrapingError::SelectorError(err.to_string())).unwrap();
-
-            let mut games_on_page = Vec::new();
-
-            for article_elem in document.select(&article_selector) {
-                let title_elem = article_elem
-                    .select(&scraper::Selector::parse(".entry-title a").unwrap())
-                    .next();
-                let pic_elem = article_elem
-                    .select(&scraper::Selector::parse(".entry-content .alignleft").unwrap())
-                    .next();
-                let desc_elem = article_elem
-                    .select(&scraper::Selector::parse("div.entry-content").unwrap())
-                    .next();
-                let hreflink_elem = article_elem
-                    .select(&scraper::Selector::parse(".entry-title > a").unwrap())
-                    .next();
-                let tag_elem = article_elem
-                    .select(
-                        &scraper::Selector::parse(".entry-content p strong:first-of-type").unwrap(),
-                    )
-                    .next();
-
-                if let (Some(title_elem), Some(pic_elem), Some(desc_elem), Some(hreflink_elem)) =
-                    (title_elem, pic_elem, desc_elem, hreflink_elem)
-                {
-                    let title = title_elem.text().collect::<String>();
-                    let img = pic_elem.value().attr("src").unwrap_or_default();
-                    let desc = desc_elem.text().collect::<String>();
-                    let href = hreflink_elem.value().attr("href").unwrap_or_default();
-                    let tag = tag_elem
-                        .map_or_else(|| "Unknown".to_string(), |e| e.text().collect::<String>());
-
-                    let magnet_link = desc_elem
-                        .select(&scraper::Selector::parse("a[href*='magnet']").unwrap())
-                        .next()
-                        .and_then(|elem| elem.value().attr("href"))
-                        .unwrap_or_default();
-
-                    if img.contains("imageban") {
-                        games_on_page.push(Game {
-                            title,
-                            img: img.to_string(),
-                            desc,
-                            magnetlink: magnet_link.to_string(),
-                            href: href.to_string(),
-                            tag: tag.to_string(),
-                        });
-                    }
-                }
-            }
-
-            Ok(games_on_page)
         })
         .collect();
 
     // Collect results into a Vec
-    let results: Vec<Result<Vec<Game>, String>> = fetch_tasks.collect().await;
+    let results: Vec<Result<CachedResponse, String>> = fetch_tasks.collect().await;
+
+    let all_not_modified = results
+        .iter()
+        .all(|result| result.as_ref().is_ok_and(|res| res.not_modified));
+    if all_not_modified && GAME_STORE.feed_len(StoredFeed::NewlyAdded)? > 0 {
+        info!("Listing pages not modified since the last scrape, keeping newly_added_games.json");
+        GAME_STORE.mark_refreshed(StoredFeed::NewlyAdded)?;
+        return Ok(());
+    }
+
+    let mut report = ScrapeReport::new(StoredFeed::NewlyAdded.key());
 
     // Process the results
     for result in results {
         match result {
-            Ok(parsed_games) => recently_up_games.extend(parsed_games),
-            Err(err_msg) => eprintln!("{}", err_msg),
+            Ok(res) => {
+                report.page_fetched();
+                for post in REPACK_POST_PARSER.parse_listing(&res.body) {
+                    report.inspect(&post, &PostField::LISTING);
+                    if post.href.is_empty() {
+                        report.drop_item("no post link");
+                    } else if !post.main_image.contains("imageban") {
+                        report.drop_item("main image not hosted on imageban");
+                    } else {
+                        recently_up_games.push(Game::from(post));
+                    }
+                }
+            }
+            Err(err_msg) => {
+                report.page_failed();
+                eprintln!("{}", err_msg);
+            }
         }
     }
 
-    // Create the directory for storing the JSON file
-    let mut binding = app_handle.path().app_data_dir().unwrap();
-    binding.push("tempGames");
+    // A post can show up on both pages when new ones push the listing.
+    let scraped = recently_up_games.len();
+    let recently_up_games = dedupe_by_game_id(recently_up_games, |game| game.id.clone());
+    report.duplicates(scraped - recently_up_games.len());
+    report.kept(recently_up_games.len());
+    record_report(report);
 
-    fs::create_dir_all(&binding).await.map_err(|e| {
-        eprintln!("Failed to create directories: {:#?}", e);
-        ScrapingError::CreatingFileError {
-            source: e,
-            fn_name: "scraping_func()".to_string(),
-        }
-    })?;
-
-    let json_path = binding.join("newly_added_games.json");
-    let temp_json_path = binding.join("newly_added_games.tmp.json");
-
-    // Handle existing games for deduplication
-    let mut existing_games: Vec<Game> = Vec::new();
-    if json_path.exists() {
-        let file_content = fs::read_to_string(&json_path).await.map_err(|e| {
-            eprintln!("Error reading the existing file: {:#?}", e);
-            ScrapingError::CreatingFileError {
-                source: e,
-                fn_name: "scraping_func()".to_string(),
-            }
-        })?;
-        existing_games = serde_json::from_str(&file_content).map_err(|e| {
-            eprintln!("Failed to parse existing JSON data: {:#?}", e);
-            ScrapingError::FileJSONError(e)
-        })?;
-    }
-
-    for (i, scraped_game) in recently_up_games.iter().enumerate() {
-        if i < existing_games.len() && scraped_game.title == existing_games[i].title {
-            println!(
-                "Game '{}' matches with the existing file. Stopping...",
-                scraped_game.title
-            );
-            return Ok(());
-        }
+    if recently_up_games.is_empty() {
+        info!("No game could be scraped, keeping newly_added_games.json");
+        return Ok(());
     }
 
-    // Write to the temp file
-    let json_data = serde_json::to_string_pretty(&recently_up_games).map_err(|e| {
-        eprintln!("Error serializing recently_up_games: {:#?}", e);
-        ScrapingError::FileJSONError(e)
-    })?;
-
-    fs::write(&temp_json_path, &json_data).await.map_err(|e| {
-        eprintln!("Error writing to the temp file: {:#?}", e);
-        ScrapingError::CreatingFileError {
-            source: e,
-            fn_name: "scraping_func()".to_string(),
-        }
-    })?;
-
-    // Rename the temp file to the final file
-    fs::rename(&temp_json_path, &json_path).await.map_err(|e| {
-        eprintln!("Error renaming temp file: {:#?}", e);
-        ScrapingError::CreatingFileError {
-            source: e,
-            fn_name: "scraping_func()".to_string(),
-        }
-    })?;
+    GAME_STORE.replace_feed(StoredFeed::NewlyAdded, &recently_up_games)?;
+    GAME_STORE.export_feed(StoredFeed::NewlyAdded)?;
 
     let duration_time_process = Instant::now() - start_time;
     info!(
@@ -216,230 +104,215 @@ pub async fn scraping_func(app_handle: tauri::AppHandle) -> Result<(), Box<Scrap
     Ok(())
 }
 
-#[tokio::main]
-pub async fn popular_games_scraping_func(
-    app_handle: tauri::AppHandle,
-) -> Result<(), Box<ScrapingError>> {
-    let start_time = Instant::now();
-    let mut popular_games: Vec<Game> = Vec::new();
+/// How many post pages are fetched at the same time.
+const POST_FETCH_CONCURRENCY: usize = 4;
+/// How many of the most recent digest entries the recently updated feed shows.
+const RECENTLY_UPDATED_FEED_SIZE: usize = 20;
 
-    //TODO: Add selection for either of the month or of the year (default will be month from now on.) Through the settings front end.
-    let url = "https://fitgirl-repacks.site/popular-repacks/";
+/// Each period keeps its own list so switching between them needs no scraping.
+pub fn popular_period_list_path(temp_games_dir: &Path, period: PopularPeriod) -> PathBuf {
+    let file_name = match period {
+        PopularPeriod::Month => "popular_games_month.json",
+        PopularPeriod::Year => "popular_games_year.json",
+    };
+    temp_games_dir.join("popular").join(file_name)
+}
 
-    let res = match CUSTOM_DNS_CLIENT.get(url).send().await {
-        Ok(response) => response,
+/// Fetch one popular post and use its first screenshot, in full size, as the carousel image.
+async fn fetch_popular_game(
+    popular_post: RepackPost,
+    report: &Mutex<ScrapeReport>,
+) -> Option<Game> {
+    let href = popular_post.href.clone();
+
+    // Make a new request to get the description, magnet link, and tag
+    let game_body = match cached_get(&href).await {
+        Ok(game_res) => game_res.body,
         Err(e) => {
-            eprintln!("Failed to get a response from URL: {}", url);
-            eprintln!("Error: {:#?}", e);
-            return Err(Box::new(ScrapingError::ReqwestError(e)));
+            eprintln!("Error getting game response: {:#?}", e);
+            report.lock().unwrap().post_unreachable();
+            return None;
         }
     };
 
-    let body = match res.text().await {
-        Ok(body) => body,
-        Err(e) => {
-            eprintln!("Failed to get a body from URL: {}", url);
-            eprintln!("Error: {:#?}", e);
-
-            return Err(Box::new(ScrapingError::ReqwestError(e)));
+    let mut post = REPACK_POST_PARSER.parse_post(&game_body, &href);
+    report
+        .lock()
+        .unwrap()
+        .check_fields(&post, &PostField::POST_PAGE);
+    post.title = popular_post.title;
+    post.href = popular_post.href;
+
+    let image_src = match post.secondary_images.first() {
+        Some(image_src) => resolve_variant(image_src)
+            .await
+            .unwrap_or_else(|| image_src.to_string()),
+        None => {
+            error!(
+                "Error Popular Games Scraping Func : no screenshot found for {}",
+                href
+            );
+            "".to_string()
         }
     };
+    post.main_image = image_src;
 
-    let document = scraper::Html::parse_document(&body);
+    Some(Game::from(post))
+}
 
-    let titles_selector = match scraper::Selector::parse(".widget-grid-view-image > a") {
-        Ok(selector) => selector,
-        Err(err) => {
-            eprintln!("Error parsing titles selector: {:#?}", err);
-            return Err(Box::new(ScrapingError::SelectorError(err.to_string())));
-        }
-    };
+/// Scrape the popular list of `period` into its own feed, returns the games stored.
+async fn scrape_popular_period(
+    source: &dyn RepackSource,
+    period: PopularPeriod,
+    count: usize,
+) -> Result<Vec<Game>, Box<ScrapingError>> {
+    let url = source.popular_url(period);
 
-    let images_selector = match scraper::Selector::parse(".widget-grid-view-image > a > img") {
-        Ok(selector) => selector,
-        Err(err) => {
-            eprintln!("Error parsing images selector: {:#?}", err);
-            return Err(Box::new(ScrapingError::SelectorError(err.to_string())));
+    let res = match cached_get(&url).await {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("Failed to get a response from URL: {}", url);
+            eprintln!("Error: {:#?}", e);
+            return Err(Box::new(ScrapingError::HttpError(e)));
         }
     };
 
-    let tag_selector = match scraper::Selector::parse(".entry-content p strong:first-of-type") {
-        Ok(selector) => selector,
-        Err(err) => {
-            eprintln!("Error parsing tag selector: {:#?}", err);
-            return Err(Box::new(ScrapingError::SelectorError(err.to_string())));
-        }
-    };
-    let description_selector = scraper::Selector::parse("div.entry-content").unwrap();
-    let magnetlink_selector = match scraper::Selector::parse("a[href*='magnet']") {
-        Ok(selector) => selector,
-        Err(err) => {
-            eprintln!("Error parsing magnetlink selector: {:#?}", err);
-            return Err(Box::new(ScrapingError::SelectorError(err.to_string())));
-        }
-    };
+    let popular_posts =
+        dedupe_by_game_id(REPACK_POST_PARSER.parse_popular_widget(&res.body), |post| {
+            game_id(&post.href)
+        });
+    let wanted = count.min(popular_posts.len());
+
+    let mut report = ScrapeReport::new(StoredFeed::from(period).key());
+    report.page_fetched();
+    for post in popular_posts.iter().take(wanted) {
+        report.inspect(
+            post,
+            &[PostField::Title, PostField::Href, PostField::MainImage],
+        );
+    }
 
-    let hreflink_selector = match scraper::Selector::parse(".widget-grid-view-image > a ") {
-        Ok(selector) => selector,
-        Err(err) => {
-            eprintln!("Error parsing hreflink selector: {:#?}", err);
-            return Err(Box::new(ScrapingError::SelectorError(err.to_string())));
-        }
-    };
+    // An unchanged list only has to be scraped again when more games are asked for.
+    if res.not_modified
+        && let Ok(mut cached_games) = GAME_STORE.feed_games(period.into())
+        && !cached_games.is_empty()
+        && cached_games.len() >= wanted
+    {
+        info!("{:?} popular list not modified, keeping it", period);
+        cached_games.truncate(wanted);
+        report.kept(cached_games.len());
+        record_report(report);
+        GAME_STORE.replace_feed(period.into(), &cached_games)?;
+        GAME_STORE.export_feed(period.into())?;
+        return Ok(cached_games);
+    }
 
-    let mut titles = document.select(&titles_selector);
-    let mut images = document.select(&images_selector);
-    let mut hreflinks = document.select(&hreflink_selector);
+    let report = Mutex::new(report);
 
-    // Prepare file path
-    let mut binding = app_handle.path().app_data_dir().unwrap();
-    binding.push("tempGames");
-    binding.push("popular_games.json");
+    // `buffered` keeps the ranking of the list while fetching several posts at once.
+    let popular_games: Vec<Game> = stream::iter(popular_posts.into_iter().take(wanted))
+        .map(|post| fetch_popular_game(post, &report))
+        .buffered(POST_FETCH_CONCURRENCY)
+        .filter_map(|game| async move { game })
+        .collect()
+        .await;
 
-    // If no file exists or games do not match, continue with the scraping
-    let mut game_count = 0;
-    while let (Some(title_elem), Some(_image_elem), Some(hreflink_elem)) =
-        (titles.next(), images.next(), hreflinks.next())
-    {
-        let title = title_elem.value().attr("title").unwrap_or_default();
-        let href = hreflink_elem.value().attr("href").unwrap_or_default();
-
-        // Make a new request to get the description, magnet link, and tag
-        let game_res = match CUSTOM_DNS_CLIENT.get(href).send().await {
-            Ok(game_res) => game_res,
-            Err(e) => {
-                eprintln!("Error getting game response: {:#?}", e);
-                continue;
-            }
-        };
+    let mut report = report.into_inner().unwrap();
+    report.kept(popular_games.len());
+    record_report(report);
 
-        let game_body = match game_res.text().await {
-            Ok(game_body) => game_body,
-            Err(e) => {
-                eprintln!("Error getting game body: {:#?}", e);
-                continue;
-            }
-        };
-
-        let game_doc = scraper::Html::parse_document(&game_body);
-
-        // Extract description, magnet link, and tag
-        let description_elem = game_doc.select(&description_selector).next();
-        let magnetlink_elem = game_doc.select(&magnetlink_selector).next();
-        let tag_elem = game_doc.select(&tag_selector).next();
-
-        let description = description_elem
-            .map(|elem| elem.text().collect::<String>())
-            .unwrap_or_default();
-        let magnetlink = magnetlink_elem
-            .and_then(|elem| elem.value().attr("href"))
-            .unwrap_or_default();
-        let tag = tag_elem
-            .map(|elem| elem.text().collect::<String>())
-            .unwrap_or_default();
-
-        let long_image_selector = match scraper::Selector::parse(
-            ".entry-content > p:nth-of-type(3) a[href] > img[src]:nth-child(1)",
-        ) {
-            Ok(selector) => selector,
-            Err(err) => {
-                eprintln!("Error parsing long_image_selector: {:#?}", err);
-                return Err(Box::new(ScrapingError::SelectorError(err.to_string())));
-            }
-        };
-
-        let image_src = {
-            let mut p_index = 3;
-            let mut long_image_elem = game_doc.select(&long_image_selector).next();
-            while long_image_elem.is_none() && p_index < 10 {
-                p_index += 1;
-                let updated_selector = scraper::Selector::parse(&format!(
-                    ".entry-content > p:nth-of-type({}) a[href] > img[src]:nth-child(1)",
-                    p_index
-                ))
-                .expect("Invalid selector");
-                long_image_elem = game_doc.select(&updated_selector).next();
-                if long_image_elem.is_some() {
-                    break;
-                }
-            }
-            if let Some(elem) = long_image_elem {
-                let image_src = elem.value().attr("src").unwrap_or_default();
-                if image_src.contains("240p") {
-                    let primary_image = image_src.replace("240p", "1080p");
-                    if check_url_status(&primary_image).await {
-                        primary_image.to_string()
-                    } else {
-                        let fallback_image = primary_image.replace("jpg.1080p.", "");
-                        if check_url_status(&fallback_image).await {
-                            fallback_image
-                        } else {
-                            image_src.to_string()
-                        }
-                    }
-                } else {
-                    image_src.to_string()
-                }
-            } else {
-                error!("Error Popular Games Scraping Func : long_image_elem is None");
-                "".to_string()
-            }
-        };
-
-        game_count += 1;
-        let popular_game = Game {
-            title: title.to_string(),
-            img: image_src.to_string(),
-            desc: description,
-            magnetlink: magnetlink.to_string(),
-            href: href.to_string(),
-            tag: tag.to_string(),
-        };
-        popular_games.push(popular_game);
-
-        if game_count >= 8 {
-            break;
-        }
+    GAME_STORE.replace_feed(period.into(), &popular_games)?;
+    GAME_STORE.export_feed(period.into())?;
+    Ok(popular_games)
+}
+
+/// Export the stored list of `period` to `popular_games.json`, the file the hub reads.
+pub async fn show_popular_period(
+    temp_games_dir: &Path,
+    period: PopularPeriod,
+) -> Result<(), Box<ScrapingError>> {
+    let games = GAME_STORE.feed_games(period.into())?;
+    if games.is_empty() {
+        return Err(Box::new(ScrapingError::GlobalError(format!(
+            "The {:?} popular list was never scraped",
+            period
+        ))));
     }
 
-    info!("Execution time: {:#?}", start_time.elapsed());
-    let json_data = match serde_json::to_string_pretty(&popular_games) {
-        Ok(json_d) => json_d,
-        Err(e) => {
-            eprintln!("Error serializing popular_games: {:#?}", e);
-            return Err(Box::new(ScrapingError::FileJSONError(e)));
-        }
-    };
+    write_json_atomic(&temp_games_dir.join("popular_games.json"), &games)?;
+    Ok(())
+}
 
-    let mut file = match tokio::fs::File::create(&binding).await {
-        Ok(file) => file,
-        Err(e) => {
-            eprintln!("File could not be created: {:#?}", e);
-            return Err(Box::new(ScrapingError::CreatingFileError {
-                source: e,
-                fn_name: "popular_games_scraping_func()".to_string(),
-            }));
-        }
-    };
+#[tokio::main]
+pub async fn popular_games_scraping_func(
+    app_handle: tauri::AppHandle,
+) -> Result<(), Box<ScrapingError>> {
+    let start_time = Instant::now();
+    // An unreadable settings file was already reported, scrape with the defaults.
+    let settings = get_gamehub_settings().unwrap_or_default();
 
-    if let Err(e) = file.write_all(json_data.as_bytes()).await {
-        eprintln!("Error writing to the file popular_games.json: {:#?}", e);
-        return Err(Box::new(ScrapingError::CreatingFileError {
-            source: e,
-            fn_name: "popular_games_scraping_func()".to_string(),
-        }));
+    let source = repack_source();
+
+    let mut temp_games_dir = app_handle.path().app_data_dir().unwrap();
+    temp_games_dir.push("tempGames");
+
+    // Both periods are kept up to date, only the selected one decides the outcome.
+    for period in [PopularPeriod::Month, PopularPeriod::Year] {
+        let result = scrape_popular_period(&*source, period, settings.popular_games_count).await;
+
+        match result {
+            Ok(games) => info!("{:?} popular list has {} games", period, games.len()),
+            Err(e) if period == settings.popular_period => return Err(e),
+            Err(e) => error!("Failed to scrape the {:?} popular list: {}", period, e),
+        }
     }
 
-    let end_time = Instant::now();
-    let duration_time_process = end_time - start_time;
+    show_popular_period(&temp_games_dir, settings.popular_period).await?;
+
     info!(
         "Data has been written to popular_games.json. Time was: {:#?}",
-        duration_time_process
+        start_time.elapsed()
     );
 
     Ok(())
 }
 
+/// Fetch the post of one digest entry, the entry is kept as the game update.
+async fn fetch_updated_game(
+    update_entry: UpdateEntry,
+    report: &Mutex<ScrapeReport>,
+    app_handle: &tauri::AppHandle,
+) -> Option<Game> {
+    let href = update_entry.post_link.clone();
+
+    let game_body = match cached_get(&href).await {
+        Ok(game_res) => game_res.body,
+        Err(e) => {
+            eprintln!(
+                "Network error while requesting game data from {}: {}",
+                href, e
+            );
+            report.lock().unwrap().post_unreachable();
+            app_handle
+                .emit(
+                    "scraping_failed",
+                    format!("Failed to fetch game data from {}.", href),
+                )
+                .unwrap();
+            return None;
+        }
+    };
+
+    let post = REPACK_POST_PARSER.parse_post(&game_body, &href);
+    report.lock().unwrap().inspect(&post, &PostField::POST_PAGE);
+
+    Some(Game {
+        href,
+        update: Some(update_entry),
+        ..Game::from(post)
+    })
+}
+
 #[tokio::main]
 pub async fn recently_updated_games_scraping_func(
     app_handle: tauri::AppHandle,
@@ -447,11 +320,24 @@ pub async fn recently_updated_games_scraping_func(
     info!("Starting recently_updated_games_scraping_func...");
 
     let start_time = Instant::now();
-    let mut recent_games: Vec<Game> = Vec::new();
-    let url = "https://fitgirl-repacks.site/category/updates-digest/";
+    let source = repack_source();
+    let url = source.updates_digest_url();
 
-    let res = match CUSTOM_DNS_CLIENT.get(url).send().await {
+    let res = match cached_get(&url).await {
         Ok(response) => response,
+        Err(e) if e.is_status() => {
+            eprintln!(
+                "Error: Failed to connect to the website or the website is down. Status is : {:#?}",
+                e.status()
+            );
+            app_handle
+                .emit(
+                    "scraping_failed",
+                    format!("Failed to connect to {}. Website might be down.", &url),
+                )
+                .unwrap();
+            return Ok(());
+        }
         Err(e) => {
             eprintln!("Network error while requesting {}: {}", &url, e);
             app_handle
@@ -463,182 +349,62 @@ pub async fn recently_updated_games_scraping_func(
                     ),
                 )
                 .unwrap();
-            return Err(Box::new(ScrapingError::ReqwestError(e)));
+            return Err(Box::new(ScrapingError::HttpError(e)));
         }
     };
 
-    if !res.status().is_success() {
-        eprintln!(
-            "Error: Failed to connect to the website or the website is down. Status is : {:#?}",
-            res.status()
+    let mut binding = app_handle.path().app_data_dir().unwrap();
+    binding.push("tempGames");
+
+    if res.not_modified && GAME_STORE.feed_len(StoredFeed::RecentlyUpdated)? > 0 {
+        info!(
+            "Updates digest not modified since the last scrape, keeping recently_updated_games.json"
         );
+        GAME_STORE.mark_refreshed(StoredFeed::RecentlyUpdated)?;
         app_handle
             .emit(
-                "scraping_failed",
-                format!("Failed to connect to {}. Website might be down.", &url),
+                "scraping_complete",
+                "Recently updated games scraping completed.",
             )
             .unwrap();
         return Ok(());
     }
 
-    let body = match res.text().await {
-        Ok(body) => body,
-        Err(e) => {
-            eprintln!("Failed to read response body from {}: {}", &url, e);
-            app_handle
-                .emit(
-                    "scraping_failed",
-                    format!("Failed to read response body from {}.", &url),
-                )
-                .unwrap();
-            return Ok(());
-        }
-    };
-
-    let document = scraper::Html::parse_document(&body);
-    let hreflink_selector =
-        scraper::Selector::parse(".su-spoiler-content > a:first-child").unwrap();
-
-    let hreflinks = document.select(&hreflink_selector);
-    let mut game_count = 0;
+    let update_entries = REPACK_POST_PARSER.parse_updates_digest(&res.body, &*source);
 
-    // Prepare paths for file operations
-    let mut binding = app_handle.path().app_data_dir().unwrap();
-    binding.push("tempGames");
-    let json_path = binding.join("recently_updated_games.json");
-    let temp_json_path = binding.join("recently_updated_games.tmp.json");
-
-    for hreflink_elem in hreflinks {
-        let href = match hreflink_elem.value().attr("href") {
-            Some(href) => href,
-            None => continue,
-        };
-
-        let game_res = match CUSTOM_DNS_CLIENT.get(href).send().await {
-            Ok(game_res) => game_res,
-            Err(e) => {
-                eprintln!(
-                    "Network error while requesting game data from {}: {}",
-                    href, e
-                );
-                app_handle
-                    .emit(
-                        "scraping_failed",
-                        format!("Failed to fetch game data from {}.", href),
-                    )
-                    .unwrap();
-                continue;
-            }
-        };
-
-        let game_body = match game_res.text().await {
-            Ok(game_body) => game_body,
-            Err(e) => {
-                eprintln!("Failed to read game response body from {}: {}", href, e);
-                app_handle
-                    .emit(
-                        "scraping_failed",
-                        format!("Failed to read game response body from {}.", href),
-                    )
-                    .unwrap();
-                continue;
-            }
-        };
-
-        let game_doc = scraper::Html::parse_document(&game_body);
-        let title_selector = scraper::Selector::parse(".entry-title").unwrap();
-        let images_selector = scraper::Selector::parse(".entry-content > p > a > img").unwrap();
-        let description_selector = scraper::Selector::parse("div.entry-content").unwrap();
-        let magnetlink_selector = scraper::Selector::parse("a[href*='magnet']").unwrap();
-        let tag_selector =
-            scraper::Selector::parse(".entry-content p strong:first-of-type").unwrap();
-
-        let title = game_doc
-            .select(&title_selector)
-            .next()
-            .map(|elem| elem.text().collect::<String>())
-            .unwrap_or_default();
-        let description = game_doc
-            .select(&description_selector)
-            .next()
-            .map(|elem| elem.text().collect::<String>())
-            .unwrap_or_default();
-        let magnetlink = game_doc
-            .select(&magnetlink_selector)
-            .next()
-            .and_then(|elem| elem.value().attr("href"))
-            .unwrap_or_default();
-        let image_src = game_doc
-            .select(&images_selector)
-            .next()
-            .and_then(|elem| elem.value().attr("src"))
-            .unwrap_or_default();
-        let tag = game_doc
-            .select(&tag_selector)
-            .next()
-            .map(|elem| elem.text().collect::<String>())
-            .unwrap_or_else(|| "Unknown".to_string());
-
-        game_count += 1;
-        recent_games.push(Game {
-            title,
-            img: image_src.to_string(),
-            desc: description,
-            magnetlink: magnetlink.to_string(),
-            href: href.to_string(),
-            tag: tag.to_string(),
-        });
+    let mut report = ScrapeReport::new(StoredFeed::RecentlyUpdated.key());
+    report.page_fetched();
 
-        if game_count >= 20 {
-            break;
-        }
+    // Every entry of the digests is kept, the hub only shows the most recent games.
+    let digest_path = binding.join("updates").join("updates_digest.json");
+    if let Err(e) = write_json_atomic(&digest_path, &update_entries) {
+        error!("Failed to write {:?}: {}", digest_path, e);
     }
 
-    let json_data = match serde_json::to_string_pretty(&recent_games) {
-        Ok(json_d) => json_d,
-        Err(e) => {
-            eprintln!("Error serializing recent_games: {:#?}", e);
-            app_handle
-                .emit(
-                    "scraping_failed",
-                    "Failed to serialize recently updated games.",
-                )
-                .unwrap();
-            return Err(Box::new(ScrapingError::FileJSONError(e)));
-        }
-    };
+    let report = Mutex::new(report);
 
-    let mut temp_file = match tokio::fs::File::create(&temp_json_path).await {
-        Ok(file) => file,
-        Err(e) => {
-            eprintln!("Temp file could not be created: {:#?}", e);
-            app_handle
-                .emit(
-                    "scraping_failed",
-                    "Failed to create temporary file for recently updated games.",
-                )
-                .unwrap();
-            return Err(Box::new(ScrapingError::CreatingFileError {
-                source: e,
-                fn_name: "recently_updated_games_scraping_func()".to_string(),
-            }));
-        }
-    };
+    // `buffered` keeps the digest order while fetching several posts at once.
+    let recent_games: Vec<Game> =
+        stream::iter(update_entries.into_iter().take(RECENTLY_UPDATED_FEED_SIZE))
+            .map(|update_entry| fetch_updated_game(update_entry, &report, &app_handle))
+            .buffered(POST_FETCH_CONCURRENCY)
+            .filter_map(|game| async move { game })
+            .collect()
+            .await;
 
-    if let Err(e) = temp_file.write_all(json_data.as_bytes()).await {
-        eprintln!("Error writing to the temporary file: {:#?}", e);
-        return Err(Box::new(ScrapingError::CreatingFileError {
-            source: e,
-            fn_name: "recently_updated_games_scraping_func()".to_string(),
-        }));
-    }
+    let mut report = report.into_inner().unwrap();
+    report.kept(recent_games.len());
+    record_report(report);
 
-    if let Err(e) = tokio::fs::rename(&temp_json_path, &json_path).await {
-        eprintln!("Error renaming temp file: {:#?}", e);
-        return Err(Box::new(ScrapingError::CreatingFileError {
-            source: e,
-            fn_name: "recently_updated_games_scraping_func()".to_string(),
-        }));
+    if let Err(e) = GAME_STORE
+        .replace_feed(StoredFeed::RecentlyUpdated, &recent_games)
+        .and_then(|()| GAME_STORE.export_feed(StoredFeed::RecentlyUpdated))
+    {
+        eprintln!("Error storing recent_games: {:#?}", e);
+        app_handle
+            .emit("scraping_failed", "Failed to store recently updated games.")
+            .unwrap();
+        return Err(Box::new(e));
     }
 
     let duration_time_process = start_time.elapsed();
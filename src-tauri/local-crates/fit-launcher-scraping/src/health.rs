@@ -0,0 +1,274 @@
+//! Health report of every scrape run.
+//!
+//! A markup change on the site doesn't make the parser fail, it makes it find
+//! nothing: empty titles, missing images, posts filtered out. Each run counts
+//! what its selectors missed and what it dropped, and a run crossing the
+//! thresholds below is flagged as degraded and announced to the frontend.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use tracing::{error, info, warn};
+
+use crate::structs::RepackPost;
+
+/// A field missing from more than this share of the articles means its selector broke.
+const MAX_MISSING_RATIO: f64 = 0.25;
+/// Dropping more than this share of the articles means a filter no longer matches.
+const MAX_DROPPED_RATIO: f64 = 0.5;
+/// How many reports are kept, older ones are forgotten.
+const KEPT_REPORTS: usize = 50;
+
+/// The fields the parser reads from an article, with the selector behind each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostField {
+    Title,
+    Href,
+    MainImage,
+    Description,
+    Magnetlink,
+    Tag,
+    SecondaryImages,
+}
+
+impl PostField {
+    /// The fields a listing article always carries.
+    pub const LISTING: [PostField; 5] = [
+        PostField::Title,
+        PostField::Href,
+        PostField::MainImage,
+        PostField::Magnetlink,
+        PostField::Tag,
+    ];
+
+    /// The fields read from a full post page, on top of the ones its link came with.
+    pub const POST_PAGE: [PostField; 4] = [
+        PostField::Description,
+        PostField::Magnetlink,
+        PostField::Tag,
+        PostField::SecondaryImages,
+    ];
+
+    fn selector(self) -> &'static str {
+        match self {
+            PostField::Title => ".entry-title",
+            PostField::Href => ".entry-title > a",
+            PostField::MainImage => ".entry-content .alignleft",
+            PostField::Description => "div.entry-content",
+            PostField::Magnetlink => "a[href*='magnet']",
+            PostField::Tag => ".entry-content p strong:first-of-type",
+            PostField::SecondaryImages => ".entry-content > p:nth-of-type(3..=5) img[src]",
+        }
+    }
+
+    fn is_missing(self, post: &RepackPost) -> bool {
+        match self {
+            PostField::Title => post.title.trim().is_empty(),
+            PostField::Href => post.href.trim().is_empty(),
+            PostField::MainImage => post.main_image.trim().is_empty(),
+            PostField::Description => post.description.trim().is_empty(),
+            PostField::Magnetlink => post.magnetlink.trim().is_empty(),
+            // The parser falls back to "Unknown" when the selector finds nothing.
+            PostField::Tag => post.tag.trim().is_empty() || post.tag == "Unknown",
+            PostField::SecondaryImages => post.secondary_images.is_empty(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrapeReport {
+    pub feed: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub pages_fetched: usize,
+    pub pages_failed: usize,
+    pub articles_seen: usize,
+    pub items_kept: usize,
+    /// How many articles each selector found nothing in, keyed by `field (selector)`.
+    pub missing_fields: BTreeMap<String, usize>,
+    /// Articles left out of the feed, by reason.
+    pub dropped: BTreeMap<String, usize>,
+    /// Post pages that didn't load, a network problem and not a sign of drift.
+    pub posts_unreachable: usize,
+    /// Posts seen twice, across pages fetched concurrently, not a sign of drift.
+    pub duplicates: usize,
+    pub degraded: bool,
+    /// What crossed the thresholds, empty for a healthy run.
+    pub problems: Vec<String>,
+    #[serde(skip)]
+    timer: Option<Instant>,
+}
+
+impl ScrapeReport {
+    pub fn new(feed: &str) -> Self {
+        ScrapeReport {
+            feed: feed.to_string(),
+            started_at: Utc::now(),
+            duration_ms: 0,
+            pages_fetched: 0,
+            pages_failed: 0,
+            articles_seen: 0,
+            items_kept: 0,
+            missing_fields: BTreeMap::new(),
+            dropped: BTreeMap::new(),
+            posts_unreachable: 0,
+            duplicates: 0,
+            degraded: false,
+            problems: Vec::new(),
+            timer: Some(Instant::now()),
+        }
+    }
+
+    pub fn page_fetched(&mut self) {
+        self.pages_fetched += 1;
+    }
+
+    pub fn page_failed(&mut self) {
+        self.pages_failed += 1;
+    }
+
+    /// Count `post` as seen and note which of `fields` its selectors missed.
+    pub fn inspect(&mut self, post: &RepackPost, fields: &[PostField]) {
+        self.articles_seen += 1;
+        self.check_fields(post, fields);
+    }
+
+    /// Note which of `fields` are missing from an article already counted as seen,
+    /// for posts completed from their own page.
+    pub fn check_fields(&mut self, post: &RepackPost, fields: &[PostField]) {
+        for field in fields {
+            if field.is_missing(post) {
+                self.missing(*field);
+            }
+        }
+    }
+
+    fn missing(&mut self, field: PostField) {
+        let key = format!("{:?} ({})", field, field.selector());
+        *self.missing_fields.entry(key).or_default() += 1;
+    }
+
+    /// Count an article that couldn't be parsed at all.
+    pub fn skip_article(&mut self, reason: &str) {
+        self.articles_seen += 1;
+        self.drop_item(reason);
+    }
+
+    pub fn drop_item(&mut self, reason: &str) {
+        *self.dropped.entry(reason.to_string()).or_default() += 1;
+    }
+
+    /// Count a post whose page didn't load, it is left out of the thresholds.
+    pub fn post_unreachable(&mut self) {
+        self.posts_unreachable += 1;
+    }
+
+    pub fn duplicates(&mut self, count: usize) {
+        self.duplicates += count;
+    }
+
+    pub fn kept(&mut self, count: usize) {
+        self.items_kept = count;
+    }
+
+    /// Check the thresholds, once every page of the run was handled.
+    fn finish(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            self.duration_ms = timer.elapsed().as_millis() as u64;
+        }
+
+        if self.pages_fetched > 0 && self.articles_seen == 0 && self.posts_unreachable == 0 {
+            self.problems.push(format!(
+                "no article found on {} fetched pages",
+                self.pages_fetched
+            ));
+        }
+
+        if self.articles_seen > 0 {
+            let seen = self.articles_seen as f64;
+            for (field, missing) in &self.missing_fields {
+                if *missing as f64 / seen > MAX_MISSING_RATIO {
+                    self.problems.push(format!(
+                        "{} missing from {} of {} articles",
+                        field, missing, self.articles_seen
+                    ));
+                }
+            }
+
+            let dropped: usize = self.dropped.values().sum();
+            if dropped as f64 / seen > MAX_DROPPED_RATIO {
+                self.problems.push(format!(
+                    "{} of {} articles dropped: {:?}",
+                    dropped, self.articles_seen, self.dropped
+                ));
+            }
+        }
+
+        self.degraded = !self.problems.is_empty();
+    }
+}
+
+struct HealthLog {
+    reports: VecDeque<ScrapeReport>,
+    /// Degraded reports not emitted yet, scrapes don't all have an `AppHandle`.
+    unannounced: Vec<ScrapeReport>,
+}
+
+static HEALTH_LOG: LazyLock<Mutex<HealthLog>> = LazyLock::new(|| {
+    Mutex::new(HealthLog {
+        reports: VecDeque::new(),
+        unannounced: Vec::new(),
+    })
+});
+
+fn health_log() -> std::sync::MutexGuard<'static, HealthLog> {
+    HEALTH_LOG
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Close `report` and keep it with the last ones.
+pub fn record_report(mut report: ScrapeReport) {
+    report.finish();
+
+    if report.degraded {
+        warn!(
+            "Scrape of {} is degraded: {}",
+            report.feed,
+            report.problems.join("; ")
+        );
+    } else {
+        info!(
+            "Scrape of {} is healthy: {} articles, {} kept",
+            report.feed, report.articles_seen, report.items_kept
+        );
+    }
+
+    let mut log = health_log();
+    if report.degraded {
+        log.unannounced.push(report.clone());
+    }
+    log.reports.push_front(report);
+    log.reports.truncate(KEPT_REPORTS);
+}
+
+/// Emit `scraper-degraded` with every degraded report recorded since the last call.
+pub fn announce_degraded_reports(app_handle: &tauri::AppHandle) {
+    let unannounced = std::mem::take(&mut health_log().unannounced);
+    for report in unannounced {
+        if let Err(e) = app_handle.emit("scraper-degraded", &report) {
+            error!("Failed to emit scraper-degraded: {}", e);
+        }
+    }
+}
+
+/// The last scrape reports, the most recent first.
+#[tauri::command]
+pub fn get_scraper_health() -> Vec<ScrapeReport> {
+    health_log().reports.iter().cloned().collect()
+}
@@ -1,5 +1,19 @@
+pub mod catalog;
 pub mod discovery;
 pub mod errors;
 pub mod global;
+pub mod health;
+pub mod parser;
+pub mod paste;
+pub mod search;
+pub mod source;
+pub mod store;
 pub mod structs;
+pub mod updates;
+pub mod variants;
 pub use global::commands::*;
+pub use health::*;
+pub use paste::*;
+pub use search::*;
+pub use store::*;
+pub use updates::*;
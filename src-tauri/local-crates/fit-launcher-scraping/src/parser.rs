@@ -0,0 +1,593 @@
+//! The one place that knows how a FitGirl repack post is laid out.
+//!
+//! Listing pages (`/category/...`), the popular repacks widget, the Updates
+//! Digest and full post pages all go through [`RepackPostParser`], so a
+//! selector fix only has to be made here.
+
+use std::sync::LazyLock;
+
+use chrono::DateTime;
+use regex::Regex;
+use scraper::{ElementRef, Html, Node, Selector};
+
+use crate::source::RepackSource;
+use crate::structs::{Mirror, MirrorKind, RepackMetadata, RepackPost, RepackSize, UpdateEntry};
+
+/// Shared parser instance, the selectors only need to be compiled once.
+pub static REPACK_POST_PARSER: LazyLock<RepackPostParser> = LazyLock::new(RepackPostParser::new);
+
+/// Screenshots live in the paragraphs following the header, we never keep more than that.
+const MAX_SECONDARY_IMAGES: usize = 5;
+
+/// Domains of the torrent trackers FitGirl mirrors her uploads to.
+const TORRENT_TRACKERS: [&str; 5] = ["1337x", "rutor", "tapochek", "rutracker", "nnmclub"];
+
+static REPACK_NUMBER_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"#(\d+)").unwrap());
+static SIZE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(from\s+)?(\d+(?:,\d{3})+(?:\.\d+)?|\d+(?:[.,]\d+)?)\s*(KB|MB|GB|TB)").unwrap()
+});
+/// `1,234` or `1,234.5`: commas followed by exactly three digits group thousands.
+static GROUPED_NUMBER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d+(?:,\d{3})+(?:\.\d+)?$").unwrap());
+/// Digest lines are separated by `<br>` or by their own block element.
+static DIGEST_LINE_BREAK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<br\s*/?>|</p>|</li>|</div>").unwrap());
+/// `v1.2 → v1.3`, `Build 123 -> Build 456`, `1.0 to 1.1`...
+static VERSION_CHANGE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(v?\d[\w.+\-]*|build\s*\d+)\s*(?:→|->|=>|\bto\b)\s*(v?\d[\w.+\-]*|build\s*\d+)",
+    )
+    .unwrap()
+});
+static VERSION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(v\d[\w.+\-]*|build\s*\d+)").unwrap());
+
+pub struct RepackPostParser {
+    article: Selector,
+    title: Selector,
+    title_link: Selector,
+    canonical_link: Selector,
+    main_image: Selector,
+    fallback_main_image: Selector,
+    content: Selector,
+    magnet: Selector,
+    anchor: Selector,
+    tag: Selector,
+    header_heading: Selector,
+    header_paragraph: Selector,
+    publish_date: Selector,
+    screenshot_paragraphs: Vec<Selector>,
+    popular_link: Selector,
+    popular_image: Selector,
+    spoiler_content: Selector,
+}
+
+impl Default for RepackPostParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RepackPostParser {
+    pub fn new() -> Self {
+        RepackPostParser {
+            article: Selector::parse("article").unwrap(),
+            title: Selector::parse(".entry-title").unwrap(),
+            title_link: Selector::parse(".entry-title > a").unwrap(),
+            canonical_link: Selector::parse("link[rel='canonical']").unwrap(),
+            main_image: Selector::parse(".entry-content .alignleft").unwrap(),
+            fallback_main_image: Selector::parse(".entry-content > p > a > img").unwrap(),
+            content: Selector::parse("div.entry-content").unwrap(),
+            magnet: Selector::parse("a[href*='magnet']").unwrap(),
+            anchor: Selector::parse("a[href]").unwrap(),
+            tag: Selector::parse(".entry-content p strong:first-of-type").unwrap(),
+            header_heading: Selector::parse(".entry-content h3").unwrap(),
+            header_paragraph: Selector::parse(".entry-content p").unwrap(),
+            publish_date: Selector::parse("time.entry-date[datetime]").unwrap(),
+            screenshot_paragraphs: (3..=5)
+                .map(|p_index| {
+                    Selector::parse(&format!(
+                        ".entry-content > p:nth-of-type({}) img[src]",
+                        p_index
+                    ))
+                    .unwrap()
+                })
+                .collect(),
+            popular_link: Selector::parse(".widget-grid-view-image > a").unwrap(),
+            popular_image: Selector::parse("img[src]").unwrap(),
+            spoiler_content: Selector::parse(".su-spoiler-content").unwrap(),
+        }
+    }
+
+    /// Parse every `<article>` of a listing page (category pages, search results...).
+    pub fn parse_listing(&self, body: &str) -> Vec<RepackPost> {
+        let document = Html::parse_document(body);
+
+        document
+            .select(&self.article)
+            .map(|article| self.parse_article(article))
+            .collect()
+    }
+
+    /// Parse a full post page. `href` is used when the page has no canonical link.
+    pub fn parse_post(&self, body: &str, href: &str) -> RepackPost {
+        let document = Html::parse_document(body);
+
+        let mut post = match document.select(&self.article).next() {
+            Some(article) => self.parse_article(article),
+            None => self.parse_article(document.root_element()),
+        };
+
+        if post.href.is_empty() {
+            post.href = document
+                .select(&self.canonical_link)
+                .next()
+                .and_then(|e| e.value().attr("href"))
+                .filter(|canonical| !canonical.is_empty())
+                .unwrap_or(href)
+                .to_string();
+        }
+
+        post
+    }
+
+    /// Parse the popular repacks grid widget.
+    ///
+    /// The widget only carries a title, a link and a thumbnail, the rest of the
+    /// post has to be filled by fetching `href` and calling [`Self::parse_post`].
+    pub fn parse_popular_widget(&self, body: &str) -> Vec<RepackPost> {
+        let document = Html::parse_document(body);
+
+        document
+            .select(&self.popular_link)
+            .filter_map(|link_elem| {
+                let href = link_elem.value().attr("href")?.trim();
+                if href.is_empty() {
+                    return None;
+                }
+
+                Some(RepackPost {
+                    title: link_elem
+                        .value()
+                        .attr("title")
+                        .unwrap_or_default()
+                        .to_string(),
+                    href: href.to_string(),
+                    main_image: link_elem
+                        .select(&self.popular_image)
+                        .next()
+                        .and_then(|e| e.value().attr("src"))
+                        .unwrap_or_default()
+                        .to_string(),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    /// Parse the Updates Digest posts of a listing page (or a single digest post).
+    ///
+    /// Entries keep the page order, newest digest first, and a game updated in
+    /// several digests only keeps its most recent entry.
+    pub fn parse_updates_digest(&self, body: &str, source: &dyn RepackSource) -> Vec<UpdateEntry> {
+        let document = Html::parse_document(body);
+
+        let mut digests: Vec<ElementRef> = document.select(&self.article).collect();
+        if digests.is_empty() {
+            digests.push(document.root_element());
+        }
+
+        let mut entries: Vec<UpdateEntry> = Vec::new();
+        for digest in digests {
+            let date = digest
+                .select(&self.publish_date)
+                .next()
+                .and_then(|e| e.value().attr("datetime"))
+                .and_then(|datetime| DateTime::parse_from_rfc3339(datetime).ok());
+
+            for spoiler in digest.select(&self.spoiler_content) {
+                for line in DIGEST_LINE_BREAK_REGEX.split(&spoiler.inner_html()) {
+                    let Some(mut entry) = self.parse_digest_line(line, source) else {
+                        continue;
+                    };
+                    if entries.iter().any(|e| e.game_id == entry.game_id) {
+                        continue;
+                    }
+                    entry.date = date;
+                    entries.push(entry);
+                }
+            }
+        }
+
+        entries
+    }
+
+    /// One digest line: the game link, the version change and maybe an update-only download.
+    fn parse_digest_line(&self, line: &str, source: &dyn RepackSource) -> Option<UpdateEntry> {
+        let fragment = Html::parse_fragment(line);
+        let links: Vec<ElementRef> = fragment.select(&self.anchor).collect();
+
+        let game_link = links
+            .iter()
+            .find(|link| {
+                link.value()
+                    .attr("href")
+                    .is_some_and(|href| source.is_post_link(href))
+            })
+            .or_else(|| links.first())?;
+        let post_link = game_link.value().attr("href")?.trim().to_string();
+        let game_title = game_link.text().collect::<String>().trim().to_string();
+        if game_title.is_empty() {
+            return None;
+        }
+
+        // Versions come after the game name, which may contain digits of its own.
+        let text = fragment.root_element().text().collect::<String>();
+        let details = text
+            .split_once(&game_title)
+            .map(|(_, rest)| rest)
+            .unwrap_or(&text);
+
+        let version = |raw: &str| raw.trim_end_matches(['.', ',', '-']).to_string();
+        let (old_version, new_version) = match VERSION_CHANGE_REGEX.captures(details) {
+            Some(caps) => (Some(version(&caps[1])), Some(version(&caps[2]))),
+            None => (
+                None,
+                VERSION_REGEX
+                    .find_iter(details)
+                    .last()
+                    .map(|m| version(m.as_str())),
+            ),
+        };
+
+        let incremental_update_link = links
+            .iter()
+            .filter(|link| link.id() != game_link.id())
+            .find(|link| {
+                link.text()
+                    .collect::<String>()
+                    .to_lowercase()
+                    .contains("update")
+            })
+            .and_then(|link| link.value().attr("href"))
+            .map(|href| href.trim().to_string());
+        let lowercase_details = details.to_lowercase();
+        let incremental_update = incremental_update_link.is_some()
+            || lowercase_details.contains("update-only")
+            || lowercase_details.contains("update only")
+            || lowercase_details.contains("incremental");
+
+        Some(UpdateEntry {
+            game_title,
+            game_id: game_id(&post_link),
+            post_link,
+            old_version,
+            new_version,
+            date: None,
+            incremental_update,
+            incremental_update_link,
+        })
+    }
+
+    fn parse_article(&self, article: ElementRef) -> RepackPost {
+        let title = article
+            .select(&self.title)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_string())
+            .unwrap_or_default();
+
+        let href = article
+            .select(&self.title_link)
+            .next()
+            .and_then(|e| e.value().attr("href"))
+            .unwrap_or_default()
+            .to_string();
+
+        let main_image = article
+            .select(&self.main_image)
+            .next()
+            .or_else(|| article.select(&self.fallback_main_image).next())
+            .and_then(|e| e.value().attr("src"))
+            .unwrap_or_default()
+            .to_string();
+
+        let content_elem = article.select(&self.content).next();
+
+        let description = content_elem
+            .map(|e| e.text().collect::<String>())
+            .unwrap_or_default();
+
+        let magnetlink = content_elem
+            .and_then(|e| e.select(&self.magnet).next())
+            .and_then(|e| e.value().attr("href"))
+            .unwrap_or_default()
+            .to_string();
+
+        // The paste link is only recognisable by its label or its host.
+        let torrent_paste_link = content_elem
+            .and_then(|e| {
+                e.select(&self.anchor).find(|a| {
+                    let href = a.value().attr("href").unwrap_or_default();
+                    a.text().collect::<String>().contains(".torrent file only")
+                        || href.contains("paste.fitgirl-repacks")
+                })
+            })
+            .and_then(|e| e.value().attr("href"))
+            .unwrap_or_default()
+            .to_string();
+
+        let tag = article
+            .select(&self.tag)
+            .next()
+            .map(|e| e.text().collect::<String>())
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        let mut secondary_images = Vec::new();
+        'paragraphs: for selector in &self.screenshot_paragraphs {
+            for element in article.select(selector) {
+                if let Some(src) = element.value().attr("src") {
+                    secondary_images.push(src.to_string());
+                }
+
+                if secondary_images.len() >= MAX_SECONDARY_IMAGES {
+                    break 'paragraphs;
+                }
+            }
+        }
+
+        let mut metadata = self.parse_metadata(article);
+        metadata.version = parse_repack_version(&title);
+        let mirrors = self.parse_mirrors(article);
+
+        RepackPost {
+            title,
+            href,
+            main_image,
+            description,
+            magnetlink,
+            torrent_paste_link,
+            secondary_images,
+            tag,
+            metadata,
+            mirrors,
+        }
+    }
+
+    /// Collect the links of every "Download Mirrors" section of the post.
+    fn parse_mirrors(&self, article: ElementRef) -> Vec<Mirror> {
+        let mut mirrors: Vec<Mirror> = Vec::new();
+
+        for heading in article.select(&self.header_heading) {
+            let heading_text = heading.text().collect::<String>().to_lowercase();
+            if !heading_text.contains("download mirrors") {
+                continue;
+            }
+
+            // Newer posts split the block in "(Direct Links)" and "(Torrent)".
+            let section_kind = if heading_text.contains("direct") {
+                MirrorKind::DirectDownload
+            } else if heading_text.contains("torrent") {
+                MirrorKind::Torrent
+            } else {
+                MirrorKind::Other
+            };
+
+            // The links live in the lists following the heading, up to the next heading.
+            // Paragraphs in between (the forum thread link...) aren't mirrors.
+            let section = heading
+                .next_siblings()
+                .filter_map(ElementRef::wrap)
+                .take_while(|e| !matches!(e.value().name(), "h1" | "h2" | "h3" | "h4"))
+                .filter(|e| matches!(e.value().name(), "ul" | "ol"));
+
+            for block in section {
+                for link in block.select(&self.anchor) {
+                    let Some(url) = link.value().attr("href") else {
+                        continue;
+                    };
+                    let label = link.text().collect::<String>();
+                    let mirror = classify_mirror(url.trim(), label.trim(), section_kind);
+
+                    if !mirror.url.is_empty() && !mirrors.iter().any(|m| m.url == mirror.url) {
+                        mirrors.push(mirror);
+                    }
+                }
+            }
+        }
+
+        mirrors
+    }
+
+    /// Read the `Label: <strong>value</strong>` pairs of the post header.
+    fn parse_metadata(&self, article: ElementRef) -> RepackMetadata {
+        let mut metadata = RepackMetadata {
+            repack_number: article
+                .select(&self.header_heading)
+                .next()
+                .and_then(|heading| {
+                    let heading_text = heading.text().collect::<String>();
+                    REPACK_NUMBER_REGEX
+                        .captures(&heading_text)
+                        .and_then(|caps| caps[1].parse().ok())
+                }),
+            published_at: article
+                .select(&self.publish_date)
+                .next()
+                .and_then(|e| e.value().attr("datetime"))
+                .and_then(|datetime| DateTime::parse_from_rfc3339(datetime).ok()),
+            ..Default::default()
+        };
+
+        let Some(header) = article.select(&self.header_paragraph).find(|p| {
+            p.text()
+                .any(|text| text.contains("Genres") || text.contains("Size:"))
+        }) else {
+            return metadata;
+        };
+
+        // Labels are plain text nodes, values are the `<strong>` right after them.
+        let mut label = String::new();
+        for child in header.children() {
+            match child.value() {
+                Node::Text(text) => label.push_str(text),
+                Node::Element(element) if element.name() == "strong" => {
+                    let value = ElementRef::wrap(child)
+                        .map(|e| e.text().collect::<String>())
+                        .unwrap_or_default();
+                    apply_header_field(&mut metadata, &label.to_lowercase(), value.trim());
+                    label.clear();
+                }
+                _ => {}
+            }
+        }
+
+        metadata
+    }
+}
+
+fn classify_mirror(url: &str, label: &str, section_kind: MirrorKind) -> Mirror {
+    let domain = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|parsed| {
+            parsed
+                .host_str()
+                .map(|h| h.trim_start_matches("www.").to_string())
+        })
+        .unwrap_or_default();
+
+    let kind = if url.starts_with("magnet:") {
+        MirrorKind::Magnet
+    } else if domain.starts_with("paste.") || label.contains(".torrent file only") {
+        MirrorKind::TorrentFile
+    } else if TORRENT_TRACKERS
+        .iter()
+        .any(|tracker| domain.contains(tracker))
+    {
+        MirrorKind::Torrent
+    } else if section_kind == MirrorKind::Other && label.to_lowercase().contains("filehoster") {
+        MirrorKind::DirectDownload
+    } else {
+        section_kind
+    };
+
+    // DDL entries are labelled "Filehoster: Name" and may point back to the post itself.
+    let host = match label.split_once(':') {
+        _ if kind == MirrorKind::Magnet => "magnet".to_string(),
+        Some((prefix, name)) if prefix.eq_ignore_ascii_case("filehoster") => {
+            name.trim().to_string()
+        }
+        _ if !domain.is_empty() => domain,
+        _ => label.to_string(),
+    };
+
+    Mirror {
+        kind,
+        host,
+        url: url.to_string(),
+    }
+}
+
+fn apply_header_field(metadata: &mut RepackMetadata, label: &str, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+
+    if label.contains("genre") {
+        metadata.genres = split_header_list(value, &[',']);
+    } else if label.contains("compan") {
+        metadata.companies = split_header_list(value, &[',']);
+    } else if label.contains("language") {
+        metadata.languages = split_header_list(value, &['/', ',']);
+    } else if label.contains("original size") {
+        metadata.original_size = parse_size(value);
+    } else if label.contains("repack size") {
+        metadata.repack_size = parse_size(value);
+    }
+}
+
+fn split_header_list(value: &str, separators: &[char]) -> Vec<String> {
+    value
+        .split(separators)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Stable identifier of a game, the slug of its post URL.
+///
+/// Titles change with every new version of a repack while the slug stays,
+/// `https://fitgirl-repacks.site/elden-ring/` gives `elden-ring`.
+pub fn game_id(url: &str) -> String {
+    url.split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Parse sizes such as `136.3 GB`, `from 71.9 GB`, `1,5 GB` or `1,234 MB`.
+pub fn parse_size(raw: &str) -> Option<RepackSize> {
+    let caps = SIZE_REGEX.captures(raw)?;
+    let amount = if GROUPED_NUMBER_REGEX.is_match(&caps[2]) {
+        caps[2].replace(',', "")
+    } else {
+        caps[2].replace(',', ".")
+    };
+    let amount: f64 = amount.parse().ok()?;
+    let multiplier: u64 = match caps[3].to_uppercase().as_str() {
+        "KB" => 1 << 10,
+        "MB" => 1 << 20,
+        "GB" => 1 << 30,
+        _ => 1 << 40,
+    };
+
+    Some(RepackSize {
+        raw: raw.to_string(),
+        bytes: (amount * multiplier as f64) as u64,
+        minimum: caps.get(1).is_some(),
+    })
+}
+
+/// The repack version carried by a post title, `Game – v1.2.3 + 2 DLCs` gives `v1.2.3`.
+pub fn parse_repack_version(title: &str) -> Option<String> {
+    VERSION_REGEX
+        .find(title)
+        .map(|m| m.as_str().trim_end_matches(['.', ',', '-']).to_string())
+}
+
+/// Whether `latest` is a newer repack version than `installed`.
+///
+/// Versions of the same kind are compared number by number, and a suffix added
+/// to the same numbers (a hotfix) is newer. Anything else (a `v1.2` replaced by
+/// a `Build 1234`...) counts as newer as soon as it differs.
+pub fn is_newer_version(installed: &str, latest: &str) -> bool {
+    fn normalize(version: &str) -> String {
+        version.trim().to_lowercase()
+    }
+    fn numbers(version: &str) -> Vec<u64> {
+        version
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|part| part.parse().ok())
+            .collect()
+    }
+
+    let (installed, latest) = (normalize(installed), normalize(latest));
+    if installed == latest {
+        return false;
+    }
+
+    let same_kind = installed.starts_with("build") == latest.starts_with("build");
+    let (installed_numbers, latest_numbers) = (numbers(&installed), numbers(&latest));
+    if same_kind && !installed_numbers.is_empty() && !latest_numbers.is_empty() {
+        // `v1.0.3-hotfix` has the numbers of `v1.0.3` and still replaces it.
+        return latest_numbers > installed_numbers
+            || (latest_numbers == installed_numbers && latest.starts_with(&installed));
+    }
+
+    true
+}
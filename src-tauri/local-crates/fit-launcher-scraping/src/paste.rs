@@ -0,0 +1,202 @@
+//! Resolves the PrivateBin pastes linked from posts (`.torrent file only`).
+//!
+//! The paste is fetched encrypted through the app HTTP client and decrypted
+//! locally with the key found in the URL fragment, the key never leaves the machine.
+
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use fit_launcher_config::client::connectivity::CONNECTIVITY;
+use fit_launcher_config::client::http::{HTTP_CLIENT, HttpError};
+use fitgirl_decrypt::{
+    Attachment, CipherInfo, Paste,
+    base64::{Engine, prelude::BASE64_STANDARD},
+};
+use reqwest::header::ACCEPT;
+use serde::Serialize;
+use tauri::Manager;
+use tempfile::NamedTempFile;
+use tracing::info;
+
+use crate::errors::PasteError;
+
+#[derive(Debug, Serialize)]
+pub struct DecryptedPaste {
+    pub attachment_name: String,
+    /// Raw `.torrent` file, when the paste holds one.
+    #[serde(skip)]
+    pub torrent: Option<Vec<u8>>,
+    /// Where the `.torrent` file has been written by [`decrypt_torrent_paste`].
+    pub torrent_path: Option<PathBuf>,
+    /// Direct download links, when the paste holds a link list instead.
+    pub ddl_links: Vec<String>,
+}
+
+/// The PrivateBin API URL of the encrypted paste behind `paste_link`.
+///
+/// `https://paste.host/?<paste id>#<key>` -> `https://paste.host/?pasteid=<paste id>`
+pub fn cipher_info_url(paste_link: &str) -> Result<String, PasteError> {
+    let (base_url, paste_info) = paste_link
+        .split_once('?')
+        .ok_or(fitgirl_decrypt::Error::IllFormedURL)?;
+    let (paste_id, _key) = paste_info
+        .split_once('#')
+        .ok_or(fitgirl_decrypt::Error::IllFormedURL)?;
+
+    Ok(format!("{}?pasteid={}", base_url, paste_id))
+}
+
+/// Fetch the encrypted paste behind `paste_link`, nothing is requested while offline.
+async fn fetch_cipher_info(paste_link: &str) -> Result<CipherInfo, PasteError> {
+    let url = cipher_info_url(paste_link)?;
+    if CONNECTIVITY.is_offline() {
+        return Err(HttpError::Offline { url }.into());
+    }
+
+    let response = HTTP_CLIENT
+        .send(HTTP_CLIENT.get(&url).header(ACCEPT, "application/json"))
+        .await?;
+    if !response.status().is_success() {
+        return Err(HttpError::Status {
+            url,
+            status: response.status(),
+        }
+        .into());
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(move |source| HttpError::Request { url, source })?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Decrypt an already fetched paste with the key of `paste_link`.
+pub fn decrypt_paste(
+    paste_link: &str,
+    cipher_info: CipherInfo,
+) -> Result<DecryptedPaste, PasteError> {
+    let paste = Paste::parse_url(paste_link)?;
+    let attachment = paste.decrypt(cipher_info)?;
+
+    decode_attachment(attachment)
+}
+
+/// Split the `data:<mime>;base64,<payload>` attachment into a torrent or a link list.
+fn decode_attachment(attachment: Attachment) -> Result<DecryptedPaste, PasteError> {
+    let (mime_type, payload) = attachment
+        .attachment
+        .strip_prefix("data:")
+        .and_then(|data_uri| data_uri.split_once(";base64,"))
+        .ok_or_else(|| {
+            PasteError::MalformedAttachment(format!(
+                "{} is not a base64 data URI",
+                attachment.attachment_name
+            ))
+        })?;
+
+    let data = BASE64_STANDARD
+        .decode(payload)
+        .map_err(fitgirl_decrypt::Error::from)?;
+
+    let mut decrypted = DecryptedPaste {
+        attachment_name: attachment.attachment_name.clone(),
+        torrent: None,
+        torrent_path: None,
+        ddl_links: Vec::new(),
+    };
+
+    if mime_type == "application/x-bittorrent" {
+        decrypted.torrent = Some(data);
+    } else if mime_type.starts_with("text/") {
+        decrypted.ddl_links = String::from_utf8_lossy(&data)
+            .split_whitespace()
+            .filter(|token| token.starts_with("http://") || token.starts_with("https://"))
+            .map(str::to_string)
+            .collect();
+    } else {
+        return Err(PasteError::MalformedAttachment(format!(
+            "unsupported attachment type {} for {}",
+            mime_type, attachment.attachment_name
+        )));
+    }
+
+    Ok(decrypted)
+}
+
+/// Name of the `attempt`th candidate for `file_name`: itself, then `name (2).torrent`...
+fn numbered_file_name(file_name: &Path, attempt: usize) -> PathBuf {
+    if attempt == 1 {
+        return file_name.to_path_buf();
+    }
+
+    let stem = file_name.file_stem().unwrap_or_default().to_string_lossy();
+    match file_name.extension() {
+        Some(extension) => format!("{} ({}).{}", stem, attempt, extension.to_string_lossy()).into(),
+        None => format!("{} ({})", stem, attempt).into(),
+    }
+}
+
+/// Save the `.torrent` of a paste in `dir` through a temp file, returns where it landed.
+///
+/// A file of the same name is never replaced: the same torrent is reused, another
+/// one gets a numbered name next to it.
+pub fn save_torrent(dir: &Path, attachment_name: &str, torrent: &[u8]) -> std::io::Result<PathBuf> {
+    // The attachment name comes from the paste, never let it leave the folder.
+    let file_name = Path::new(attachment_name)
+        .file_name()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("paste.torrent"));
+
+    std::fs::create_dir_all(dir)?;
+    let mut temp_file = NamedTempFile::new_in(dir)?;
+    temp_file.write_all(torrent)?;
+
+    for attempt in 1..=100 {
+        let path = dir.join(numbered_file_name(&file_name, attempt));
+        if std::fs::read(&path).is_ok_and(|existing| existing == torrent) {
+            return Ok(path);
+        }
+
+        match temp_file.persist_noclobber(&path) {
+            Ok(_) => return Ok(path),
+            Err(e) if e.error.kind() == ErrorKind::AlreadyExists => temp_file = e.file,
+            Err(e) => return Err(e.error),
+        }
+    }
+
+    Err(std::io::Error::new(
+        ErrorKind::AlreadyExists,
+        format!("every name for {:?} is taken", file_name),
+    ))
+}
+
+/// Fetch and decrypt a paste link, the `.torrent` it holds is saved in `tempGames/torrents`.
+#[tauri::command]
+pub async fn decrypt_torrent_paste(
+    app_handle: tauri::AppHandle,
+    paste_link: String,
+) -> Result<DecryptedPaste, PasteError> {
+    // A link that can't be decrypted isn't worth a request.
+    Paste::parse_url(&paste_link)?;
+    let cipher_info = fetch_cipher_info(&paste_link).await?;
+
+    let mut decrypted = decrypt_paste(&paste_link, cipher_info)?;
+
+    if let Some(torrent) = decrypted.torrent.clone() {
+        let mut torrents_dir = app_handle.path().app_data_dir()?;
+        torrents_dir.push("tempGames");
+        torrents_dir.push("torrents");
+
+        let attachment_name = decrypted.attachment_name.clone();
+        let torrent_path = tokio::task::spawn_blocking(move || {
+            save_torrent(&torrents_dir, &attachment_name, &torrent)
+        })
+        .await
+        .map_err(std::io::Error::other)??;
+        info!("Decrypted paste torrent written to {:?}", torrent_path);
+        decrypted.torrent_path = Some(torrent_path);
+    }
+
+    Ok(decrypted)
+}
@@ -0,0 +1,367 @@
+//! Offline search over the local catalog.
+//!
+//! The index merges the sitemap catalog (every post, title only) with the
+//! posts of the game store (genres, companies, languages, sizes) and answers
+//! without touching the network. It is rebuilt when either of them changes.
+
+use std::{collections::BTreeMap, time::SystemTime};
+
+use chrono::{DateTime, Utc};
+use fit_launcher_config::client::connectivity::Served;
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, State};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::{catalog::Catalog, store::GAME_STORE, structs::RepackMetadata};
+
+pub const SEARCH_PAGE_SIZE: usize = 30;
+
+/// Below this similarity a query word is not considered a typo of an indexed word.
+const MIN_WORD_SIMILARITY: f64 = 0.75;
+
+const TITLE_WEIGHT: f64 = 3.0;
+const GENRE_WEIGHT: f64 = 1.5;
+const COMPANY_WEIGHT: f64 = 1.0;
+const LANGUAGE_WEIGHT: f64 = 0.5;
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(default)]
+pub struct SearchFilters {
+    pub genres: Vec<String>,
+    pub companies: Vec<String>,
+    pub languages: Vec<String>,
+    /// Upper bound on the smallest possible download, in bytes.
+    pub max_repack_size: Option<u64>,
+}
+
+impl SearchFilters {
+    fn is_empty(&self) -> bool {
+        self.genres.is_empty()
+            && self.companies.is_empty()
+            && self.languages.is_empty()
+            && self.max_repack_size.is_none()
+    }
+
+    fn matches(&self, metadata: Option<&RepackMetadata>) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        // Posts that were never scraped can't be checked against any filter.
+        let Some(metadata) = metadata else {
+            return false;
+        };
+
+        let contains_all = |wanted: &[String], values: &[String]| {
+            wanted
+                .iter()
+                .all(|w| values.iter().any(|v| v.eq_ignore_ascii_case(w)))
+        };
+
+        contains_all(&self.genres, &metadata.genres)
+            && contains_all(&self.companies, &metadata.companies)
+            && contains_all(&self.languages, &metadata.languages)
+            && self.max_repack_size.is_none_or(|max| {
+                metadata
+                    .repack_size
+                    .as_ref()
+                    .is_some_and(|size| size.bytes <= max)
+            })
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchHit {
+    /// See [`crate::parser::game_id`].
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    pub image: Option<String>,
+    pub metadata: Option<RepackMetadata>,
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub hits: Vec<SearchHit>,
+}
+
+struct IndexedGame {
+    hit: SearchHit,
+    title_words: Vec<String>,
+    genre_words: Vec<String>,
+    company_words: Vec<String>,
+    language_words: Vec<String>,
+}
+
+impl IndexedGame {
+    fn new(hit: SearchHit) -> Self {
+        let metadata_words = |pick: fn(&RepackMetadata) -> &Vec<String>| {
+            hit.metadata
+                .as_ref()
+                .map(|m| pick(m).iter().flat_map(|v| tokenize(v)).collect())
+                .unwrap_or_default()
+        };
+
+        IndexedGame {
+            title_words: tokenize(&hit.title),
+            genre_words: metadata_words(|m| &m.genres),
+            company_words: metadata_words(|m| &m.companies),
+            language_words: metadata_words(|m| &m.languages),
+            hit,
+        }
+    }
+
+    /// Sum of the best match of every query word, `None` if one word matches nothing.
+    fn score(&self, query_words: &[String], query: &str) -> Option<f64> {
+        let mut score = 0.0;
+
+        for query_word in query_words {
+            let best = [
+                (TITLE_WEIGHT, &self.title_words),
+                (GENRE_WEIGHT, &self.genre_words),
+                (COMPANY_WEIGHT, &self.company_words),
+                (LANGUAGE_WEIGHT, &self.language_words),
+            ]
+            .into_iter()
+            .map(|(weight, words)| weight * best_word_similarity(query_word, words))
+            .fold(0.0, f64::max);
+
+            if best == 0.0 {
+                return None;
+            }
+            score += best;
+        }
+
+        // Whole-phrase hits in the title win over scattered word hits.
+        if self.hit.title.to_lowercase().contains(query) {
+            score += TITLE_WEIGHT;
+        }
+
+        Some(score)
+    }
+}
+
+/// 1.0 for an exact word, slightly less for a prefix, then typo tolerance.
+fn best_word_similarity(query_word: &str, words: &[String]) -> f64 {
+    words
+        .iter()
+        .map(|word| {
+            if word == query_word {
+                1.0
+            } else if word.starts_with(query_word) {
+                0.9
+            } else if query_word.chars().count() < 4 {
+                // Too short for edit distance to mean anything.
+                0.0
+            } else {
+                let similarity = strsim::normalized_damerau_levenshtein(query_word, word);
+                if similarity >= MIN_WORD_SIMILARITY {
+                    similarity * 0.8
+                } else {
+                    0.0
+                }
+            }
+        })
+        .fold(0.0, f64::max)
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+pub struct SearchIndex {
+    games: Vec<IndexedGame>,
+}
+
+impl SearchIndex {
+    /// Merge the catalog with the scraped games, scraped data wins for a same post.
+    pub fn build(catalog: &Catalog, scraped: Vec<SearchHit>) -> SearchIndex {
+        let mut hits: BTreeMap<String, SearchHit> = catalog
+            .entries
+            .values()
+            .map(|entry| {
+                (
+                    entry.slug.clone(),
+                    SearchHit {
+                        id: entry.slug.clone(),
+                        url: entry.url.clone(),
+                        title: entry.title.clone(),
+                        image: entry.image.clone(),
+                        metadata: None,
+                        score: 0.0,
+                    },
+                )
+            })
+            .collect();
+
+        for game in scraped {
+            hits.entry(game.id.clone())
+                .and_modify(|existing| {
+                    existing.title = game.title.clone();
+                    existing.metadata = game.metadata.clone();
+                    if game.image.is_some() {
+                        existing.image = game.image.clone();
+                    }
+                })
+                .or_insert(game);
+        }
+
+        SearchIndex {
+            games: hits.into_values().map(IndexedGame::new).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    pub fn search(&self, query: &str, filters: &SearchFilters, page: usize) -> SearchResults {
+        let query = query.trim().to_lowercase();
+        let query_words = tokenize(&query);
+
+        let mut hits: Vec<SearchHit> = self
+            .games
+            .iter()
+            .filter(|game| filters.matches(game.hit.metadata.as_ref()))
+            .filter_map(|game| {
+                let score = if query_words.is_empty() {
+                    0.0
+                } else {
+                    game.score(&query_words, &query)?
+                };
+                Some(SearchHit {
+                    score,
+                    ..game.hit.clone()
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.title.cmp(&b.title))
+        });
+
+        SearchResults {
+            total: hits.len(),
+            page,
+            page_size: SEARCH_PAGE_SIZE,
+            hits: hits
+                .into_iter()
+                .skip(page * SEARCH_PAGE_SIZE)
+                .take(SEARCH_PAGE_SIZE)
+                .collect(),
+        }
+    }
+}
+
+/// What an index was built from, it is rebuilt as soon as one of them changes.
+#[derive(PartialEq, Eq)]
+struct IndexSources {
+    catalog_modified: Option<SystemTime>,
+    store_revision: u64,
+}
+
+struct BuiltIndex {
+    sources: IndexSources,
+    /// When the newest data it holds was scraped.
+    refreshed_at: Option<DateTime<Utc>>,
+    index: SearchIndex,
+}
+
+#[derive(Default)]
+pub struct SearchIndexState(RwLock<Option<BuiltIndex>>);
+
+/// Every scraped post, from the game store.
+fn stored_games() -> Vec<SearchHit> {
+    let games = GAME_STORE.all_games().unwrap_or_else(|e| {
+        warn!(
+            "Indexing the catalog alone, the game store can't be read: {}",
+            e
+        );
+        Vec::new()
+    });
+
+    games
+        .into_iter()
+        .map(|game| SearchHit {
+            id: game.id,
+            url: game.href,
+            title: game.title,
+            image: Some(game.img).filter(|img| !img.is_empty()),
+            metadata: Some(game.metadata),
+            score: 0.0,
+        })
+        .collect()
+}
+
+/// Fuzzy search over every known post, works without network access.
+#[tauri::command]
+pub async fn search_games(
+    app_handle: tauri::AppHandle,
+    search_index: State<'_, SearchIndexState>,
+    query: String,
+    filters: Option<SearchFilters>,
+    page: Option<usize>,
+) -> Result<Served<SearchResults>, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+    let catalog_path = app_data_dir.join("sitemaps").join("catalog.json");
+    let catalog_modified = tokio::fs::metadata(&catalog_path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let sources = IndexSources {
+        catalog_modified,
+        store_revision: GAME_STORE.revision(),
+    };
+
+    let is_stale = match &*search_index.0.read().await {
+        Some(built) => built.sources != sources,
+        None => true,
+    };
+
+    if is_stale {
+        let catalog = Catalog::load(&catalog_path).await;
+        let index = SearchIndex::build(&catalog, stored_games());
+        info!("Search index rebuilt with {} games", index.len());
+
+        let store_updated = GAME_STORE.latest_update().unwrap_or_else(|e| {
+            warn!("Failed to read when the game store was last updated: {}", e);
+            None
+        });
+        let refreshed_at = catalog_modified
+            .map(DateTime::<Utc>::from)
+            .max(store_updated);
+        *search_index.0.write().await = Some(BuiltIndex {
+            sources,
+            refreshed_at,
+            index,
+        });
+    }
+
+    let guard = search_index.0.read().await;
+    let built = guard.as_ref().ok_or("Search index is not available")?;
+
+    Ok(Served::new(
+        built.index.search(
+            &query,
+            &filters.unwrap_or_default(),
+            page.unwrap_or_default(),
+        ),
+        built.refreshed_at,
+    ))
+}
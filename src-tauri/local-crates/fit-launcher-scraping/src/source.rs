@@ -0,0 +1,93 @@
+//! The site the repacks are scraped from.
+//!
+//! Scrapers ask the [`RepackSource`] for every URL they fetch instead of
+//! building them, the FitGirl one reads its base URL and paths from the
+//! gamehub settings.
+
+use fit_launcher_config::settings::config::get_gamehub_settings;
+use fit_launcher_config::settings::creation::{PopularPeriod, SourceSettings};
+
+pub trait RepackSource: Send + Sync {
+    /// Scheme and host of the site, without a trailing slash.
+    fn base_url(&self) -> &str;
+
+    /// Page `page` (starting at 1) of the newest repacks listing.
+    fn listing_page_url(&self, page: usize) -> String;
+
+    fn popular_url(&self, period: PopularPeriod) -> String;
+
+    /// Listing of the Updates Digest posts.
+    fn updates_digest_url(&self) -> String;
+
+    fn sitemap_index_url(&self) -> String;
+
+    /// Whether `href` points to this site, subdomains (the paste host...) excluded.
+    fn is_post_link(&self, href: &str) -> bool {
+        let host = self
+            .base_url()
+            .split_once("://")
+            .map_or(self.base_url(), |(_, host)| host);
+        href.split_once("://").is_some_and(|(_, rest)| {
+            rest.strip_prefix(host)
+                .is_some_and(|path| path.is_empty() || path.starts_with('/'))
+        })
+    }
+}
+
+pub struct FitGirlSource {
+    settings: SourceSettings,
+}
+
+impl FitGirlSource {
+    pub fn new(settings: SourceSettings) -> Self {
+        FitGirlSource { settings }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url(), path.trim_matches('/'))
+    }
+}
+
+impl Default for FitGirlSource {
+    fn default() -> Self {
+        FitGirlSource::new(SourceSettings::default())
+    }
+}
+
+impl RepackSource for FitGirlSource {
+    fn base_url(&self) -> &str {
+        self.settings.base_url.trim_end_matches('/')
+    }
+
+    fn listing_page_url(&self, page: usize) -> String {
+        format!(
+            "{}/page/{}",
+            self.url(&self.settings.new_repacks_path),
+            page
+        )
+    }
+
+    fn popular_url(&self, period: PopularPeriod) -> String {
+        let path = match period {
+            PopularPeriod::Month => &self.settings.popular_month_path,
+            PopularPeriod::Year => &self.settings.popular_year_path,
+        };
+        format!("{}/", self.url(path))
+    }
+
+    fn updates_digest_url(&self) -> String {
+        format!("{}/", self.url(&self.settings.updates_digest_path))
+    }
+
+    fn sitemap_index_url(&self) -> String {
+        self.url(&self.settings.sitemap_index_path)
+    }
+}
+
+/// The source configured in the gamehub settings, read again on every call so
+/// a changed setting applies to the next scrape.
+pub fn repack_source() -> Box<dyn RepackSource> {
+    // An unreadable settings file was already reported, scrape the default site.
+    let settings = get_gamehub_settings().unwrap_or_default();
+    Box::new(FitGirlSource::new(settings.source))
+}
@@ -0,0 +1,867 @@
+//! Embedded SQLite store of everything the scrapers collect.
+//!
+//! Each feed is replaced in a single transaction, so a crash mid-scrape leaves
+//! the previous feed in place. The JSON files under `tempGames` the frontend
+//! reads are exports of the store, written through a temporary file and
+//! rebuilt from it on startup.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+use chrono::{DateTime, Utc};
+use directories::BaseDirs;
+use fit_launcher_config::client::cache::now_secs;
+use fit_launcher_config::client::connectivity::Served;
+use fit_launcher_config::settings::creation::PopularPeriod;
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+use tracing::{error, info, warn};
+
+use crate::errors::ScrapingError;
+use crate::global::functions::popular_period_list_path;
+use crate::structs::{Game, GamePage, Mirror, RepackMetadata, UpdateEntry};
+
+/// Upgrade a database from one schema version to the next.
+type Migration = fn(&Connection) -> rusqlite::Result<()>;
+
+/// `MIGRATIONS[i]` upgrades a database of schema `i + 1` to schema `i + 2`,
+/// push one with every change of [`SCHEMA`].
+const MIGRATIONS: [Migration; 3] = [
+    add_feed_refreshes,
+    add_image_variants,
+    add_feed_presentation,
+];
+
+const SCHEMA_VERSION: i32 = MIGRATIONS.len() as i32 + 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS posts (
+    id TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    image TEXT NOT NULL,
+    description TEXT NOT NULL,
+    magnetlink TEXT NOT NULL,
+    torrent_paste_link TEXT NOT NULL DEFAULT '',
+    tag TEXT NOT NULL,
+    metadata TEXT NOT NULL,
+    mirrors TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS feeds (
+    feed TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    post_id TEXT NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    update_entry TEXT,
+    title TEXT,
+    image TEXT,
+    PRIMARY KEY (feed, position)
+);
+CREATE TABLE IF NOT EXISTS images (
+    post_id TEXT NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    PRIMARY KEY (post_id, position)
+);
+CREATE TABLE IF NOT EXISTS colors (
+    image_url TEXT PRIMARY KEY,
+    colors TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS feed_refreshes (
+    feed TEXT PRIMARY KEY,
+    refreshed_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS image_variants (
+    source_url TEXT PRIMARY KEY,
+    resolved_url TEXT,
+    checked_at INTEGER NOT NULL
+);
+";
+
+/// Schema 2: when each feed was last checked against the site.
+///
+/// The first databases had no paste link either, it is added when missing.
+fn add_feed_refreshes(connection: &Connection) -> rusqlite::Result<()> {
+    let has_paste_link: bool = connection.query_row(
+        "SELECT EXISTS (SELECT 1 FROM pragma_table_info('posts') WHERE name = 'torrent_paste_link')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_paste_link {
+        connection.execute_batch(
+            "ALTER TABLE posts ADD COLUMN torrent_paste_link TEXT NOT NULL DEFAULT '';",
+        )?;
+    }
+
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS feed_refreshes (
+            feed TEXT PRIMARY KEY,
+            refreshed_at INTEGER NOT NULL
+        );",
+    )
+}
+
+/// Schema 3: the resolved variant of every screenshot thumbnail.
+fn add_image_variants(connection: &Connection) -> rusqlite::Result<()> {
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS image_variants (
+            source_url TEXT PRIMARY KEY,
+            resolved_url TEXT,
+            checked_at INTEGER NOT NULL
+        );",
+    )
+}
+
+/// Schema 4: the title and picture a feed shows a post under, when it has its own.
+fn add_feed_presentation(connection: &Connection) -> rusqlite::Result<()> {
+    connection.execute_batch(
+        "ALTER TABLE feeds ADD COLUMN title TEXT;
+         ALTER TABLE feeds ADD COLUMN image TEXT;",
+    )
+}
+
+/// The lists the scrapers maintain, in the order they are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoredFeed {
+    NewlyAdded,
+    PopularMonth,
+    PopularYear,
+    RecentlyUpdated,
+    Discovery,
+    /// The last post looked up on its own, see `get_singular_game_info`.
+    Singular,
+}
+
+impl StoredFeed {
+    pub const ALL: [StoredFeed; 6] = [
+        StoredFeed::NewlyAdded,
+        StoredFeed::PopularMonth,
+        StoredFeed::PopularYear,
+        StoredFeed::RecentlyUpdated,
+        StoredFeed::Discovery,
+        StoredFeed::Singular,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            StoredFeed::NewlyAdded => "newly_added",
+            StoredFeed::PopularMonth => "popular_month",
+            StoredFeed::PopularYear => "popular_year",
+            StoredFeed::RecentlyUpdated => "recently_updated",
+            StoredFeed::Discovery => "discovery",
+            StoredFeed::Singular => "singular",
+        }
+    }
+
+    /// The popular lists show a post under the widget title and a screenshot,
+    /// those are kept on the feed and never replace the post's own.
+    fn has_own_presentation(self) -> bool {
+        matches!(self, StoredFeed::PopularMonth | StoredFeed::PopularYear)
+    }
+
+    /// The JSON file the frontend reads this feed from.
+    pub fn export_path(self, app_data_dir: &Path) -> PathBuf {
+        let temp_games_dir = app_data_dir.join("tempGames");
+        match self {
+            StoredFeed::NewlyAdded => temp_games_dir.join("newly_added_games.json"),
+            StoredFeed::PopularMonth => {
+                popular_period_list_path(&temp_games_dir, PopularPeriod::Month)
+            }
+            StoredFeed::PopularYear => {
+                popular_period_list_path(&temp_games_dir, PopularPeriod::Year)
+            }
+            StoredFeed::RecentlyUpdated => temp_games_dir.join("recently_updated_games.json"),
+            StoredFeed::Discovery => temp_games_dir.join("discovery").join("games_list.json"),
+            StoredFeed::Singular => temp_games_dir.join("singular_game_temp.json"),
+        }
+    }
+}
+
+impl From<PopularPeriod> for StoredFeed {
+    fn from(period: PopularPeriod) -> Self {
+        match period {
+            PopularPeriod::Month => StoredFeed::PopularMonth,
+            PopularPeriod::Year => StoredFeed::PopularYear,
+        }
+    }
+}
+
+/// One row of `posts`, borrowed from whichever shape the scraper produced.
+struct PostRow<'a> {
+    id: &'a str,
+    url: &'a str,
+    title: &'a str,
+    image: &'a str,
+    description: &'a str,
+    magnetlink: &'a str,
+    torrent_paste_link: &'a str,
+    tag: &'a str,
+    metadata: &'a RepackMetadata,
+    mirrors: &'a [Mirror],
+    /// `None` keeps the screenshots already stored for the post.
+    secondary_images: Option<&'a [String]>,
+}
+
+impl<'a> From<&'a Game> for PostRow<'a> {
+    fn from(game: &'a Game) -> Self {
+        PostRow {
+            id: &game.id,
+            url: &game.href,
+            title: &game.title,
+            image: &game.img,
+            description: &game.desc,
+            magnetlink: &game.magnetlink,
+            torrent_paste_link: "",
+            tag: &game.tag,
+            metadata: &game.metadata,
+            mirrors: &game.mirrors,
+            secondary_images: None,
+        }
+    }
+}
+
+impl<'a> From<&'a GamePage> for PostRow<'a> {
+    fn from(page: &'a GamePage) -> Self {
+        PostRow {
+            id: &page.game_id,
+            url: &page.game_href,
+            title: &page.game_title,
+            image: &page.game_main_image,
+            description: &page.game_description,
+            magnetlink: &page.game_magnetlink,
+            torrent_paste_link: &page.game_torrent_paste_link,
+            tag: &page.game_tags,
+            metadata: &page.game_metadata,
+            mirrors: &page.game_mirrors,
+            secondary_images: Some(&page.game_secondary_images),
+        }
+    }
+}
+
+/// A feed entry as read back, with the post fields `Game` has no room for.
+struct FeedRow {
+    game: Game,
+    torrent_paste_link: String,
+}
+
+/// Write `value` as JSON next to `path` first, then move it in place.
+///
+/// Every write goes through its own temp file, concurrent writers of the same
+/// file can't move each other's half-written data.
+pub fn write_json_atomic<T: Serialize + ?Sized>(
+    path: &Path,
+    value: &T,
+) -> Result<(), ScrapingError> {
+    let creating_file_error = |source| ScrapingError::CreatingFileError {
+        source,
+        fn_name: "write_json_atomic()".to_string(),
+    };
+
+    let parent_dir = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(parent_dir).map_err(creating_file_error)?;
+
+    let mut temp_file = NamedTempFile::new_in(parent_dir).map_err(creating_file_error)?;
+    temp_file
+        .write_all(serde_json::to_string_pretty(value)?.as_bytes())
+        .map_err(creating_file_error)?;
+    temp_file
+        .persist(path)
+        .map_err(|e| creating_file_error(e.error))?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageVariant {
+    pub resolved_url: Option<String>,
+    /// Unix timestamp, in seconds.
+    pub checked_at: i64,
+}
+
+pub struct GameStore {
+    /// The app data directory, feeds are exported under its `tempGames`.
+    data_dir: PathBuf,
+    connection: Mutex<Connection>,
+    /// Bumped by every feed replacement, tells when derived data is out of date.
+    revision: AtomicU64,
+}
+
+pub static GAME_STORE: LazyLock<GameStore> = LazyLock::new(|| {
+    let data_dir = BaseDirs::new()
+        .expect("Failed to determine base directories")
+        .data_dir()
+        .join("com.fitlauncher.carrotrub");
+
+    GameStore::open(data_dir.clone()).unwrap_or_else(|e| {
+        error!("Failed to open the game store, keeping it in memory: {}", e);
+        GameStore::new(
+            data_dir,
+            Connection::open_in_memory().expect("Failed to open an in-memory game store"),
+        )
+        .expect("Failed to create the in-memory game store")
+    })
+});
+
+impl GameStore {
+    /// Open (or create) `games.db` in `data_dir`.
+    pub fn open(data_dir: PathBuf) -> Result<Self, ScrapingError> {
+        std::fs::create_dir_all(&data_dir).map_err(|e| ScrapingError::CreatingFileError {
+            source: e,
+            fn_name: "GameStore::open()".to_string(),
+        })?;
+        let connection = Connection::open(data_dir.join("games.db"))?;
+
+        // WAL keeps the last committed state readable whatever happens to a write.
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.pragma_update(None, "synchronous", "NORMAL")?;
+
+        GameStore::new(data_dir, connection)
+    }
+
+    fn new(data_dir: PathBuf, connection: Connection) -> Result<Self, ScrapingError> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        GameStore::migrate(&connection)?;
+
+        Ok(GameStore {
+            data_dir,
+            connection: Mutex::new(connection),
+            revision: AtomicU64::new(0),
+        })
+    }
+
+    fn migrate(connection: &Connection) -> Result<(), ScrapingError> {
+        let version: i32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(ScrapingError::GlobalError(format!(
+                "games.db has schema {}, this version only knows up to {}",
+                version, SCHEMA_VERSION
+            )));
+        }
+
+        // A failed upgrade leaves the database as it was, it is tried again next start.
+        let transaction = connection.unchecked_transaction()?;
+        if version == 0 {
+            transaction.execute_batch(SCHEMA)?;
+        } else {
+            for migration in &MIGRATIONS[(version - 1) as usize..] {
+                migration(&transaction)?;
+            }
+        }
+        transaction.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        // A panic while holding the lock can't leave a transaction half applied.
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// `keep_presentation` leaves the title and picture of an already stored post alone.
+    fn upsert_post(
+        transaction: &Transaction,
+        post: &PostRow,
+        keep_presentation: bool,
+    ) -> Result<(), ScrapingError> {
+        transaction.execute(
+            "INSERT INTO posts (id, url, title, image, description, magnetlink,
+                torrent_paste_link, tag, metadata, mirrors, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT(id) DO UPDATE SET
+                url = excluded.url,
+                title = CASE WHEN ?12 THEN posts.title ELSE excluded.title END,
+                image = CASE WHEN ?12 THEN posts.image ELSE excluded.image END,
+                description = excluded.description,
+                magnetlink = excluded.magnetlink,
+                torrent_paste_link = CASE excluded.torrent_paste_link
+                    WHEN '' THEN posts.torrent_paste_link
+                    ELSE excluded.torrent_paste_link END,
+                tag = excluded.tag,
+                metadata = excluded.metadata,
+                mirrors = excluded.mirrors,
+                updated_at = excluded.updated_at",
+            params![
+                post.id,
+                post.url,
+                post.title,
+                post.image,
+                post.description,
+                post.magnetlink,
+                post.torrent_paste_link,
+                post.tag,
+                serde_json::to_string(post.metadata)?,
+                serde_json::to_string(post.mirrors)?,
+                now_secs() as i64,
+                keep_presentation,
+            ],
+        )?;
+
+        if let Some(images) = post.secondary_images {
+            transaction.execute("DELETE FROM images WHERE post_id = ?1", params![post.id])?;
+            for (position, url) in images.iter().enumerate() {
+                transaction.execute(
+                    "INSERT INTO images (post_id, position, url) VALUES (?1, ?2, ?3)",
+                    params![post.id, position as i64, url],
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn replace_feed_rows<'a>(
+        &self,
+        feed: StoredFeed,
+        rows: impl Iterator<Item = (PostRow<'a>, Option<&'a UpdateEntry>)>,
+    ) -> Result<(), ScrapingError> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+
+        transaction.execute("DELETE FROM feeds WHERE feed = ?1", params![feed.key()])?;
+        let own_presentation = feed.has_own_presentation();
+        let mut position: i64 = 0;
+        for (post, update) in rows {
+            if post.id.is_empty() {
+                warn!(
+                    "Skipping {:?} of feed {:?}, it has no post link",
+                    post.title, feed
+                );
+                continue;
+            }
+
+            GameStore::upsert_post(&transaction, &post, own_presentation)?;
+            transaction.execute(
+                "INSERT INTO feeds (feed, position, post_id, update_entry, title, image)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    feed.key(),
+                    position,
+                    post.id,
+                    update.map(serde_json::to_string).transpose()?,
+                    own_presentation.then_some(post.title),
+                    own_presentation.then_some(post.image),
+                ],
+            )?;
+            position += 1;
+        }
+
+        GameStore::touch_feed(&transaction, feed)?;
+        transaction.commit()?;
+        self.revision.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Changes with every feed replacement, see [`crate::search`].
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    fn touch_feed(connection: &Connection, feed: StoredFeed) -> Result<(), ScrapingError> {
+        connection.execute(
+            "INSERT INTO feed_refreshes (feed, refreshed_at) VALUES (?1, ?2)
+             ON CONFLICT(feed) DO UPDATE SET refreshed_at = excluded.refreshed_at",
+            params![feed.key(), now_secs() as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Note that `feed` was checked against the site and is still current.
+    pub fn mark_refreshed(&self, feed: StoredFeed) -> Result<(), ScrapingError> {
+        GameStore::touch_feed(&self.connection(), feed)
+    }
+
+    /// When `feed` was last known to match the site, `None` when it never was.
+    pub fn refreshed_at(&self, feed: StoredFeed) -> Result<Option<DateTime<Utc>>, ScrapingError> {
+        let connection = self.connection();
+        let refreshed_at: Option<i64> = connection
+            .query_row(
+                "SELECT refreshed_at FROM feed_refreshes WHERE feed = ?1",
+                params![feed.key()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(refreshed_at.and_then(|secs| DateTime::from_timestamp(secs, 0)))
+    }
+
+    /// Replace the content of `feed` with `games`, in their order.
+    pub fn replace_feed(&self, feed: StoredFeed, games: &[Game]) -> Result<(), ScrapingError> {
+        self.replace_feed_rows(
+            feed,
+            games
+                .iter()
+                .map(|game| (PostRow::from(game), game.update.as_ref())),
+        )
+    }
+
+    /// Replace the discovery feed, which also carries the screenshots of every post.
+    pub fn replace_discovery(&self, pages: &[GamePage]) -> Result<(), ScrapingError> {
+        self.replace_feed_rows(
+            StoredFeed::Discovery,
+            pages.iter().map(|page| (PostRow::from(page), None)),
+        )
+    }
+
+    fn feed_rows(&self, feed: StoredFeed) -> Result<Vec<FeedRow>, ScrapingError> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT posts.id, posts.url, COALESCE(feeds.title, posts.title),
+                COALESCE(feeds.image, posts.image), posts.description,
+                posts.magnetlink, posts.tag, posts.metadata, posts.mirrors,
+                feeds.update_entry, posts.torrent_paste_link
+             FROM feeds JOIN posts ON posts.id = feeds.post_id
+             WHERE feeds.feed = ?1
+             ORDER BY feeds.position",
+        )?;
+
+        let rows = statement.query_map(params![feed.key()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+                row.get::<_, String>(8)?,
+                row.get::<_, Option<String>>(9)?,
+                row.get::<_, String>(10)?,
+            ))
+        })?;
+
+        let mut feed_rows = Vec::new();
+        for row in rows {
+            let (id, href, title, img, desc, magnetlink, tag, metadata, mirrors, update, paste) =
+                row?;
+            feed_rows.push(FeedRow {
+                game: Game {
+                    id,
+                    title,
+                    img,
+                    desc,
+                    magnetlink,
+                    href,
+                    tag,
+                    metadata: serde_json::from_str(&metadata)?,
+                    mirrors: serde_json::from_str(&mirrors)?,
+                    update: update.as_deref().map(serde_json::from_str).transpose()?,
+                },
+                torrent_paste_link: paste,
+            });
+        }
+
+        Ok(feed_rows)
+    }
+
+    /// How many games `feed` holds, `0` when it was never scraped.
+    pub fn feed_len(&self, feed: StoredFeed) -> Result<usize, ScrapingError> {
+        let connection = self.connection();
+        let len: i64 = connection.query_row(
+            "SELECT COUNT(*) FROM feeds WHERE feed = ?1",
+            params![feed.key()],
+            |row| row.get(0),
+        )?;
+        Ok(len as usize)
+    }
+
+    /// The games of `feed`, in their order.
+    pub fn feed_games(&self, feed: StoredFeed) -> Result<Vec<Game>, ScrapingError> {
+        Ok(self
+            .feed_rows(feed)?
+            .into_iter()
+            .map(|row| row.game)
+            .collect())
+    }
+
+    fn post_images(&self, post_id: &str) -> Result<Vec<String>, ScrapingError> {
+        let connection = self.connection();
+        let mut statement =
+            connection.prepare("SELECT url FROM images WHERE post_id = ?1 ORDER BY position")?;
+        let images = statement
+            .query_map(params![post_id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(images)
+    }
+
+    /// The discovery feed, with the screenshots of every post.
+    pub fn discovery_pages(&self) -> Result<Vec<GamePage>, ScrapingError> {
+        let mut pages = Vec::new();
+        for row in self.feed_rows(StoredFeed::Discovery)? {
+            let game_secondary_images = self.post_images(&row.game.id)?;
+            pages.push(GamePage {
+                game_id: row.game.id,
+                game_title: row.game.title,
+                game_main_image: row.game.img,
+                game_description: row.game.desc,
+                game_magnetlink: row.game.magnetlink,
+                game_torrent_paste_link: row.torrent_paste_link,
+                game_secondary_images,
+                game_tags: row.game.tag,
+                game_href: row.game.href,
+                game_metadata: row.game.metadata,
+                game_mirrors: row.game.mirrors,
+            });
+        }
+        Ok(pages)
+    }
+
+    /// When the post `id` was last scraped.
+    pub fn game_updated_at(&self, id: &str) -> Result<Option<DateTime<Utc>>, ScrapingError> {
+        let connection = self.connection();
+        let updated_at: Option<i64> = connection
+            .query_row(
+                "SELECT updated_at FROM posts WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(updated_at.and_then(|secs| DateTime::from_timestamp(secs, 0)))
+    }
+
+    /// A single post, whatever feed it was scraped from.
+    pub fn game(&self, id: &str) -> Result<Option<Game>, ScrapingError> {
+        let connection = self.connection();
+        let row = connection
+            .query_row(
+                "SELECT url, title, image, description, magnetlink, tag, metadata, mirrors
+                 FROM posts WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, String>(4)?,
+                        row.get::<_, String>(5)?,
+                        row.get::<_, String>(6)?,
+                        row.get::<_, String>(7)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        let Some((href, title, img, desc, magnetlink, tag, metadata, mirrors)) = row else {
+            return Ok(None);
+        };
+        Ok(Some(Game {
+            id: id.to_string(),
+            title,
+            img,
+            desc,
+            magnetlink,
+            href,
+            tag,
+            metadata: serde_json::from_str(&metadata)?,
+            mirrors: serde_json::from_str(&mirrors)?,
+            update: None,
+        }))
+    }
+
+    /// Every stored post, whatever feeds it belongs to, by title.
+    pub fn all_games(&self) -> Result<Vec<Game>, ScrapingError> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT id, url, title, image, description, magnetlink, tag, metadata, mirrors
+             FROM posts ORDER BY title",
+        )?;
+
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+                row.get::<_, String>(8)?,
+            ))
+        })?;
+
+        let mut games = Vec::new();
+        for row in rows {
+            let (id, href, title, img, desc, magnetlink, tag, metadata, mirrors) = row?;
+            games.push(Game {
+                id,
+                title,
+                img,
+                desc,
+                magnetlink,
+                href,
+                tag,
+                metadata: serde_json::from_str(&metadata)?,
+                mirrors: serde_json::from_str(&mirrors)?,
+                update: None,
+            });
+        }
+        Ok(games)
+    }
+
+    /// When the most recently scraped post was, `None` for an empty store.
+    pub fn latest_update(&self) -> Result<Option<DateTime<Utc>>, ScrapingError> {
+        let connection = self.connection();
+        let updated_at: Option<i64> =
+            connection.query_row("SELECT MAX(updated_at) FROM posts", [], |row| row.get(0))?;
+        Ok(updated_at.and_then(|secs| DateTime::from_timestamp(secs, 0)))
+    }
+
+    /// The colors extracted from `image_url`, as they were stored.
+    pub fn colors(&self, image_url: &str) -> Result<Option<String>, ScrapingError> {
+        let connection = self.connection();
+        Ok(connection
+            .query_row(
+                "SELECT colors FROM colors WHERE image_url = ?1",
+                params![image_url],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn set_colors(&self, image_url: &str, colors: &str) -> Result<(), ScrapingError> {
+        let connection = self.connection();
+        connection.execute(
+            "INSERT INTO colors (image_url, colors, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(image_url) DO UPDATE SET
+                colors = excluded.colors,
+                updated_at = excluded.updated_at",
+            params![image_url, colors, now_secs() as i64],
+        )?;
+        Ok(())
+    }
+
+    /// The variant `source_url` was last resolved to, see [`crate::variants`].
+    pub fn image_variant(&self, source_url: &str) -> Result<Option<ImageVariant>, ScrapingError> {
+        let connection = self.connection();
+        Ok(connection
+            .query_row(
+                "SELECT resolved_url, checked_at FROM image_variants WHERE source_url = ?1",
+                params![source_url],
+                |row| {
+                    Ok(ImageVariant {
+                        resolved_url: row.get(0)?,
+                        checked_at: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    /// Remember what `source_url` resolved to, `None` when no variant exists.
+    pub fn set_image_variant(
+        &self,
+        source_url: &str,
+        resolved_url: Option<&str>,
+    ) -> Result<(), ScrapingError> {
+        let connection = self.connection();
+        connection.execute(
+            "INSERT INTO image_variants (source_url, resolved_url, checked_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(source_url) DO UPDATE SET
+                resolved_url = excluded.resolved_url,
+                checked_at = excluded.checked_at",
+            params![source_url, resolved_url, now_secs() as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Forget the palettes and variants of `urls`, as thumbnails or as resolved variants.
+    pub fn forget_images(&self, urls: &[String]) -> Result<(), ScrapingError> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        for url in urls {
+            transaction.execute("DELETE FROM colors WHERE image_url = ?1", params![url])?;
+            transaction.execute(
+                "DELETE FROM image_variants WHERE source_url = ?1 OR resolved_url = ?1",
+                params![url],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Forget every palette and variant, they are extracted and resolved again on demand.
+    pub fn clear_images(&self) -> Result<(), ScrapingError> {
+        let connection = self.connection();
+        connection.execute_batch("DELETE FROM colors; DELETE FROM image_variants;")?;
+        Ok(())
+    }
+
+    /// Write `feed` to its JSON file.
+    pub fn export_feed(&self, feed: StoredFeed) -> Result<(), ScrapingError> {
+        let path = feed.export_path(&self.data_dir);
+        match feed {
+            StoredFeed::Discovery => write_json_atomic(&path, &self.discovery_pages()?),
+            _ => write_json_atomic(&path, &self.feed_games(feed)?),
+        }
+    }
+
+    /// Fill a feed the store has never seen from the file an older version wrote.
+    ///
+    /// A file that can't be read is removed, the next scrape writes it again.
+    fn import_legacy_feed(&self, feed: StoredFeed) -> Result<(), ScrapingError> {
+        let path = feed.export_path(&self.data_dir);
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Ok(());
+        };
+
+        let imported = match feed {
+            StoredFeed::Discovery => serde_json::from_str::<Vec<GamePage>>(&content)
+                .map_err(ScrapingError::from)
+                .and_then(|pages| self.replace_discovery(&pages)),
+            _ => serde_json::from_str::<Vec<Game>>(&content)
+                .map_err(ScrapingError::from)
+                .and_then(|games| self.replace_feed(feed, &games)),
+        };
+
+        if let Err(e) = imported {
+            warn!("{:?} can't be imported, rebuilding it: {}", path, e);
+            std::fs::remove_file(&path).map_err(|e| ScrapingError::CreatingFileError {
+                source: e,
+                fn_name: "import_legacy_feed()".to_string(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Rebuild every feed file from the store, replacing anything half written.
+    pub fn export_all(&self) {
+        for feed in StoredFeed::ALL {
+            let exported = match self.feed_len(feed) {
+                Ok(0) => self.import_legacy_feed(feed),
+                Ok(_) => self.export_feed(feed),
+                Err(e) => Err(e),
+            };
+            match exported {
+                Ok(()) => info!("Exported the {:?} feed", feed),
+                Err(e) => error!("Failed to export the {:?} feed: {}", feed, e),
+            }
+        }
+    }
+}
+
+/// The games of one feed, straight from the store.
+#[tauri::command]
+pub fn get_feed_games(feed: StoredFeed) -> Result<Served<Vec<Game>>, String> {
+    let games = GAME_STORE.feed_games(feed).map_err(|e| e.to_string())?;
+    let refreshed_at = GAME_STORE.refreshed_at(feed).map_err(|e| e.to_string())?;
+    Ok(Served::new(games, refreshed_at))
+}
+
+/// A single stored game by its id, see [`crate::parser::game_id`].
+#[tauri::command]
+pub fn get_stored_game(id: String) -> Result<Served<Option<Game>>, String> {
+    let game = GAME_STORE.game(&id).map_err(|e| e.to_string())?;
+    let updated_at = GAME_STORE.game_updated_at(&id).map_err(|e| e.to_string())?;
+    Ok(Served::new(game, updated_at))
+}
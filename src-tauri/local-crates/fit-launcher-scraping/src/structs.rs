@@ -1,27 +1,49 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SingularGame {
-    pub title: String,
-    pub img: String,
-    pub desc: String,
-    pub magnetlink: String,
-    pub href: String,
-    pub tag: String,
+use crate::parser::game_id;
+
+/// Keep the first of the items sharing a [`game_id`], in order.
+///
+/// Items without an id (no post link) are all kept.
+pub fn dedupe_by_game_id<T>(items: Vec<T>, id_of: impl Fn(&T) -> String) -> Vec<T> {
+    let mut seen: HashSet<String> = HashSet::new();
+    items
+        .into_iter()
+        .filter(|item| {
+            let id = id_of(item);
+            id.is_empty() || seen.insert(id)
+        })
+        .collect()
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Game {
+    /// See [`game_id`].
+    #[serde(default)]
+    pub id: String,
     pub title: String,
     pub img: String,
     pub desc: String,
     pub magnetlink: String,
     pub href: String,
     pub tag: String,
+    #[serde(default)]
+    pub metadata: RepackMetadata,
+    #[serde(default)]
+    pub mirrors: Vec<Mirror>,
+    /// What changed, for games coming from the Updates Digest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update: Option<UpdateEntry>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct GamePage {
+    /// See [`game_id`].
+    #[serde(default)]
+    pub game_id: String,
     pub game_title: String,
     pub game_main_image: String,
     pub game_description: String,
@@ -30,9 +52,139 @@ pub struct GamePage {
     pub game_secondary_images: Vec<String>,
     pub game_tags: String,
     pub game_href: String,
+    #[serde(default)]
+    pub game_metadata: RepackMetadata,
+    #[serde(default)]
+    pub game_mirrors: Vec<Mirror>,
+}
+
+/// A size as written in a post header, e.g. `136.3 GB` or `from 71.9 GB`.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RepackSize {
+    /// The text exactly as it appears on the site.
+    pub raw: String,
+    pub bytes: u64,
+    /// `true` for "from X GB" sizes, where optional files can be skipped
+    /// (selective download) and `bytes` is the smallest possible download.
+    pub minimum: bool,
+}
+
+/// The standard FitGirl header fields, parsed out of the post body.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct RepackMetadata {
+    pub repack_number: Option<u32>,
+    pub genres: Vec<String>,
+    pub companies: Vec<String>,
+    pub languages: Vec<String>,
+    pub original_size: Option<RepackSize>,
+    pub repack_size: Option<RepackSize>,
+    pub published_at: Option<DateTime<FixedOffset>>,
+    /// Read from the post title, see [`crate::parser::parse_repack_version`].
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MirrorKind {
+    Magnet,
+    /// A torrent tracker page (1337x, RuTor, Tapochek...).
+    Torrent,
+    /// A paste holding the `.torrent` file, see the paste decryption.
+    TorrentFile,
+    /// A direct download host.
+    DirectDownload,
+    Other,
+}
+
+/// One entry of a post "Download Mirrors" block.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Mirror {
+    pub kind: MirrorKind,
+    pub host: String,
+    pub url: String,
+}
+
+/// Every field the scrapers know how to read from a repack post, whether it
+/// came from a listing article or from the full post page.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct RepackPost {
+    pub title: String,
+    pub href: String,
+    pub main_image: String,
+    pub description: String,
+    pub magnetlink: String,
+    pub torrent_paste_link: String,
+    pub secondary_images: Vec<String>,
+    pub tag: String,
+    pub metadata: RepackMetadata,
+    pub mirrors: Vec<Mirror>,
+}
+
+impl From<RepackPost> for Game {
+    fn from(post: RepackPost) -> Self {
+        Game {
+            id: game_id(&post.href),
+            title: post.title,
+            img: post.main_image,
+            desc: post.description,
+            magnetlink: post.magnetlink,
+            href: post.href,
+            tag: post.tag,
+            metadata: post.metadata,
+            mirrors: post.mirrors,
+            update: None,
+        }
+    }
+}
+
+impl From<RepackPost> for GamePage {
+    fn from(post: RepackPost) -> Self {
+        GamePage {
+            game_id: game_id(&post.href),
+            game_title: post.title,
+            game_main_image: post.main_image,
+            game_description: post.description,
+            game_magnetlink: post.magnetlink,
+            game_torrent_paste_link: post.torrent_paste_link,
+            game_secondary_images: post.secondary_images,
+            game_tags: post.tag,
+            game_href: post.href,
+            game_metadata: post.metadata,
+            game_mirrors: post.mirrors,
+        }
+    }
+}
+
+/// One post listed in the site post sitemaps.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CatalogEntry {
+    pub url: String,
+    pub slug: String,
+    /// Derived from the slug until the post itself gets scraped.
+    pub title: String,
+    pub lastmod: Option<DateTime<FixedOffset>>,
+    pub image: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct GameImages {
     my_all_images: Vec<String>,
 }
+
+/// One line of an Updates Digest post.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct UpdateEntry {
+    pub game_title: String,
+    /// Link to the updated repack post.
+    pub post_link: String,
+    /// See [`game_id`].
+    #[serde(default)]
+    pub game_id: String,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    /// Publication date of the digest the entry comes from.
+    pub date: Option<DateTime<FixedOffset>>,
+    /// The digest offers an update-only download on top of the full repack.
+    pub incremental_update: bool,
+    pub incremental_update_link: Option<String>,
+}
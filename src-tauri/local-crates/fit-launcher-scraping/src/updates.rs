@@ -0,0 +1,220 @@
+//! Detect repacks of installed games that got a newer version on the site.
+//!
+//! Library entries remember the repack version and the post they were
+//! installed from, those are compared with the Updates Digest and, for games
+//! the digest does not mention, with the current title of their post. Entries
+//! without a recorded version fall back to the title they were downloaded under.
+//!
+//! A post is only fetched again once its `lastmod` in the catalog moved, the
+//! version found on it is kept in `post_checks.json` next to the digest.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use chrono::{DateTime, FixedOffset};
+use fit_launcher_config::client::cache::cached_get;
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+use tracing::{error, info, warn};
+
+use crate::catalog::Catalog;
+use crate::errors::ScrapingError;
+use crate::parser::{REPACK_POST_PARSER, game_id, is_newer_version, parse_repack_version};
+use crate::store::write_json_atomic;
+use crate::structs::UpdateEntry;
+
+/// The fields of a `downloaded_games.json` entry the checker cares about,
+/// the frontend owns the rest of it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct LibraryEntry {
+    torrent_extern_info: LibraryExternInfo,
+    installed_version: Option<String>,
+    post_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LibraryExternInfo {
+    title: String,
+    href: String,
+}
+
+/// An installed game whose post now carries a newer repack.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutdatedGame {
+    pub title: String,
+    pub post_url: String,
+    pub installed_version: String,
+    pub latest_version: String,
+    /// The digest entry announcing the new version, when there is one.
+    pub update: Option<UpdateEntry>,
+}
+
+/// The version last read on a post the digest did not mention.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct PostCheck {
+    /// `lastmod` of the post in the catalog when it was fetched.
+    lastmod: Option<DateTime<FixedOffset>>,
+    version: Option<String>,
+}
+
+async fn read_json<T: for<'de> Deserialize<'de> + Default>(path: &Path) -> T {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Failed to parse {:?}: {}", path, e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Latest version of the post at `post_url`, read from its title.
+async fn latest_post_version(post_url: &str) -> Result<Option<String>, ScrapingError> {
+    let body = cached_get(post_url).await?.body;
+    Ok(REPACK_POST_PARSER
+        .parse_post(&body, post_url)
+        .metadata
+        .version)
+}
+
+/// Every library entry whose installed repack is older than the one on the site.
+pub async fn find_outdated_games(
+    app_handle: &tauri::AppHandle,
+) -> Result<Vec<OutdatedGame>, ScrapingError> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| ScrapingError::GlobalError(e.to_string()))?;
+    let updates_dir = app_data_dir.join("tempGames").join("updates");
+
+    Ok(outdated_library_games(
+        &app_data_dir
+            .join("library")
+            .join("downloadedGames")
+            .join("downloaded_games.json"),
+        &updates_dir.join("updates_digest.json"),
+        &app_data_dir.join("sitemaps").join("catalog.json"),
+        &updates_dir.join("post_checks.json"),
+        latest_post_version,
+    )
+    .await)
+}
+
+/// Compare the library at `library_path` with the digest at `digest_path`.
+///
+/// Posts the digest does not mention are read with `latest_version`, unless
+/// `checks_path` holds their version and their catalog `lastmod` didn't move.
+/// Entries without a version, recorded or in their title, are skipped.
+pub async fn outdated_library_games(
+    library_path: &Path,
+    digest_path: &Path,
+    catalog_path: &Path,
+    checks_path: &Path,
+    latest_version: impl AsyncFn(&str) -> Result<Option<String>, ScrapingError>,
+) -> Vec<OutdatedGame> {
+    let library: Vec<LibraryEntry> = read_json(library_path).await;
+    let digest: Vec<UpdateEntry> = read_json(digest_path).await;
+    let catalog = Catalog::load(catalog_path).await;
+    let mut post_checks: BTreeMap<String, PostCheck> = read_json(checks_path).await;
+    let mut checks_changed = false;
+
+    // The digest is ordered from the most recent, keep the first mention of each game.
+    let mut latest_updates: HashMap<String, &UpdateEntry> = HashMap::new();
+    for entry in &digest {
+        latest_updates
+            .entry(game_id(&entry.post_link))
+            .or_insert(entry);
+    }
+
+    let mut outdated_games: Vec<OutdatedGame> = Vec::new();
+
+    for entry in library {
+        let Some(installed_version) = entry
+            .installed_version
+            .filter(|v| !v.is_empty())
+            .or_else(|| parse_repack_version(&entry.torrent_extern_info.title))
+        else {
+            continue;
+        };
+        let post_url = entry
+            .post_url
+            .filter(|url| !url.is_empty())
+            .unwrap_or(entry.torrent_extern_info.href);
+        if post_url.is_empty() {
+            continue;
+        }
+
+        let update = latest_updates.get(&game_id(&post_url)).copied();
+        let lastmod = catalog
+            .entries
+            .get(&post_url)
+            .and_then(|catalog_entry| catalog_entry.lastmod);
+        let latest_version = match update.and_then(|u| u.new_version.clone()) {
+            Some(version) => Some(version),
+            None => match post_checks.get(&post_url) {
+                Some(check) if check.lastmod == lastmod => check.version.clone(),
+                _ => match latest_version(&post_url).await {
+                    Ok(version) => {
+                        post_checks.insert(
+                            post_url.clone(),
+                            PostCheck {
+                                lastmod,
+                                version: version.clone(),
+                            },
+                        );
+                        checks_changed = true;
+                        version
+                    }
+                    Err(e) => {
+                        warn!("Failed to check {} for updates: {}", post_url, e);
+                        continue;
+                    }
+                },
+            },
+        };
+
+        let Some(latest_version) = latest_version else {
+            continue;
+        };
+        if is_newer_version(&installed_version, &latest_version) {
+            outdated_games.push(OutdatedGame {
+                title: entry.torrent_extern_info.title,
+                post_url,
+                installed_version,
+                latest_version,
+                update: update.cloned(),
+            });
+        }
+    }
+
+    if checks_changed && let Err(e) = write_json_atomic(checks_path, &post_checks) {
+        error!("Failed to write {:?}: {}", checks_path, e);
+    }
+
+    outdated_games
+}
+
+/// Check the library and emit `update-available` with every outdated game, if any.
+pub async fn notify_outdated_games(app_handle: &tauri::AppHandle) {
+    match find_outdated_games(app_handle).await {
+        Ok(outdated_games) if outdated_games.is_empty() => {
+            info!("Every installed game is up to date");
+        }
+        Ok(outdated_games) => {
+            info!("{} installed games have an update", outdated_games.len());
+            if let Err(e) = app_handle.emit("update-available", &outdated_games) {
+                error!("Failed to emit update-available: {}", e);
+            }
+        }
+        Err(e) => error!("Error checking installed games for updates: {}", e),
+    }
+}
+
+/// Check the library right away, the outdated games are returned and not emitted.
+#[tauri::command]
+pub async fn check_game_updates(app_handle: tauri::AppHandle) -> Result<Vec<OutdatedGame>, String> {
+    find_outdated_games(&app_handle)
+        .await
+        .map_err(|e| e.to_string())
+}
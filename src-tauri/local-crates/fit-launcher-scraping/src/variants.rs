@@ -0,0 +1,103 @@
+//! Full size variants of the screenshot thumbnails.
+//!
+//! Posts link `240p` thumbnails, the full picture is either the `1080p`
+//! variant or the URL without any size. Which one exists is found with `HEAD`
+//! requests, the answer is kept in the game store and only checked again once
+//! it is older than [`VARIANT_TTL`].
+
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use fit_launcher_config::client::cache::now_secs;
+use fit_launcher_config::client::http::HTTP_CLIENT;
+use futures::{StreamExt, stream};
+use tokio::sync::Semaphore;
+use tracing::{error, warn};
+
+use crate::store::GAME_STORE;
+
+/// How long a found variant is trusted.
+const VARIANT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// How long a thumbnail without any variant is left alone, one may be uploaded later.
+const MISSING_VARIANT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// `HEAD` requests in flight, shared by every scrape resolving at the same time.
+const MAX_CONCURRENT_PROBES: usize = 8;
+
+static PROBE_PERMITS: LazyLock<Semaphore> = LazyLock::new(|| Semaphore::new(MAX_CONCURRENT_PROBES));
+
+/// The variants worth trying for `source_url`, the best first.
+fn candidates(source_url: &str) -> Vec<String> {
+    if !source_url.contains("240p") {
+        return Vec::new();
+    }
+
+    let full_size = source_url.replace("240p", "1080p");
+    let stripped = full_size.replace("jpg.1080p.", "");
+    vec![full_size, stripped]
+}
+
+/// Whether `url` exists, `None` when the host couldn't tell.
+async fn probe(url: &str) -> Option<bool> {
+    let _permit = PROBE_PERMITS
+        .acquire()
+        .await
+        .expect("The probe semaphore is never closed");
+
+    match HTTP_CLIENT.url_exists(url).await {
+        Ok(exists) => Some(exists),
+        Err(e) => {
+            warn!("Failed to check {}: {}", url, e);
+            None
+        }
+    }
+}
+
+/// The full size variant of the thumbnail `source_url`, `None` if it has none.
+pub async fn resolve_variant(source_url: &str) -> Option<String> {
+    let stored = GAME_STORE.image_variant(source_url).unwrap_or_else(|e| {
+        error!("Failed to read the variant of {}: {}", source_url, e);
+        None
+    });
+
+    if let Some(stored) = &stored {
+        let ttl = match stored.resolved_url {
+            Some(_) => VARIANT_TTL,
+            None => MISSING_VARIANT_TTL,
+        };
+        if now_secs() as i64 - stored.checked_at < ttl.as_secs() as i64 {
+            return stored.resolved_url.clone();
+        }
+    }
+
+    let mut resolved = None;
+    let mut conclusive = true;
+    for candidate in candidates(source_url) {
+        match probe(&candidate).await {
+            Some(true) => {
+                resolved = Some(candidate);
+                break;
+            }
+            Some(false) => {}
+            None => conclusive = false,
+        }
+    }
+
+    // A host that didn't answer says nothing, keep what was known before.
+    if resolved.is_none() && !conclusive {
+        return stored.and_then(|stored| stored.resolved_url);
+    }
+
+    if let Err(e) = GAME_STORE.set_image_variant(source_url, resolved.as_deref()) {
+        error!("Failed to store the variant of {}: {}", source_url, e);
+    }
+    resolved
+}
+
+/// [`resolve_variant`] of every thumbnail, in their order.
+pub async fn resolve_variants(source_urls: Vec<String>) -> Vec<Option<String>> {
+    stream::iter(source_urls)
+        .map(|source_url| async move { resolve_variant(&source_url).await })
+        .buffered(MAX_CONCURRENT_PROBES)
+        .collect()
+        .await
+}
@@ -0,0 +1,78 @@
+use fit_launcher_scraping::catalog::{parse_post_sitemap, parse_sitemap_index, title_from_slug};
+
+fn load_fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read_to_string(path).expect("fixture should exist")
+}
+
+#[test]
+fn keeps_only_the_post_sitemaps() {
+    let sitemaps = parse_sitemap_index(&load_fixture("sitemap_index.xml"));
+
+    let locs: Vec<&str> = sitemaps
+        .iter()
+        .map(|sitemap| sitemap.loc.as_str())
+        .collect();
+    assert_eq!(
+        locs,
+        [
+            "https://fitgirl-repacks.site/post-sitemap.xml",
+            "https://fitgirl-repacks.site/post-sitemap2.xml",
+            "https://fitgirl-repacks.site/post-sitemap3.xml",
+        ]
+    );
+    assert_eq!(
+        sitemaps[1].lastmod.map(|date| date.to_rfc3339()),
+        Some("2025-01-12T15:02:44+00:00".to_string())
+    );
+    // Without a lastmod the sitemap is downloaded on every refresh.
+    assert!(sitemaps[2].lastmod.is_none());
+}
+
+#[test]
+fn reads_the_posts_of_a_sitemap() {
+    let entries = parse_post_sitemap(&load_fixture("post_sitemap.xml"));
+
+    // The home page is listed too, it is not a post.
+    let slugs: Vec<&str> = entries.iter().map(|entry| entry.slug.as_str()).collect();
+    assert_eq!(
+        slugs,
+        [
+            "cyberpunk-2077-ultimate-edition",
+            "hollow-knight",
+            "stalker-2-heart-of-chornobyl",
+        ]
+    );
+
+    let cyberpunk = &entries[0];
+    assert_eq!(
+        cyberpunk.url,
+        "https://fitgirl-repacks.site/cyberpunk-2077-ultimate-edition/"
+    );
+    assert_eq!(cyberpunk.title, "Cyberpunk 2077 Ultimate Edition");
+    assert_eq!(
+        cyberpunk.lastmod.map(|date| date.to_rfc3339()),
+        Some("2025-01-12T15:02:44+00:00".to_string())
+    );
+    // Only the first picture of a post is its cover.
+    assert_eq!(
+        cyberpunk.image.as_deref(),
+        Some("https://i3.imageban.ru/out/2023/09/26/cyberpunk-cover.jpg")
+    );
+
+    let hollow_knight = &entries[1];
+    assert!(hollow_knight.lastmod.is_none());
+    assert!(hollow_knight.image.is_none());
+
+    assert_eq!(
+        entries[2].lastmod.map(|date| date.to_rfc3339()),
+        Some("2024-11-21T08:15:00+03:00".to_string())
+    );
+}
+
+#[test]
+fn titles_posts_from_their_slug() {
+    assert_eq!(title_from_slug("hollow-knight"), "Hollow Knight");
+    assert_eq!(title_from_slug("lies-of-p--deluxe"), "Lies Of P Deluxe");
+    assert_eq!(title_from_slug(""), "");
+}
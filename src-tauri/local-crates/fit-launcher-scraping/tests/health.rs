@@ -0,0 +1,88 @@
+use fit_launcher_scraping::health::{PostField, ScrapeReport, get_scraper_health, record_report};
+use fit_launcher_scraping::structs::RepackPost;
+
+fn post(main_image: &str) -> RepackPost {
+    RepackPost {
+        title: "Elden Ring".to_string(),
+        href: "https://fitgirl-repacks.site/elden-ring/".to_string(),
+        main_image: main_image.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Record `report` and read it back closed, feeds are unique to each test.
+fn recorded(report: ScrapeReport) -> ScrapeReport {
+    let feed = report.feed.clone();
+    record_report(report);
+    get_scraper_health()
+        .into_iter()
+        .find(|report| report.feed == feed)
+        .unwrap()
+}
+
+fn with_missing_images(feed: &str, missing: usize) -> ScrapeReport {
+    let mut report = ScrapeReport::new(feed);
+    report.page_fetched();
+    for index in 0..4 {
+        let image = if index < missing {
+            ""
+        } else {
+            "https://i5.imageban.ru/elden-ring.jpg"
+        };
+        report.inspect(
+            &post(image),
+            &[PostField::Title, PostField::Href, PostField::MainImage],
+        );
+    }
+    report
+}
+
+#[test]
+fn flags_a_field_missing_from_more_than_a_quarter_of_the_articles() {
+    let healthy = recorded(with_missing_images("missing_one_image", 1));
+    assert!(!healthy.degraded, "{:?}", healthy.problems);
+
+    let degraded = recorded(with_missing_images("missing_two_images", 2));
+    assert!(degraded.degraded);
+    assert_eq!(degraded.problems.len(), 1);
+    assert!(degraded.problems[0].starts_with("MainImage"));
+}
+
+fn with_dropped_articles(feed: &str, dropped: usize) -> ScrapeReport {
+    let mut report = with_missing_images(feed, 0);
+    for _ in 0..dropped {
+        report.drop_item("no post link");
+    }
+    report
+}
+
+#[test]
+fn flags_more_than_half_of_the_articles_dropped() {
+    let healthy = recorded(with_dropped_articles("dropped_two", 2));
+    assert!(!healthy.degraded, "{:?}", healthy.problems);
+
+    let degraded = recorded(with_dropped_articles("dropped_three", 3));
+    assert!(degraded.degraded);
+    assert!(degraded.problems[0].starts_with("3 of 4 articles dropped"));
+}
+
+#[test]
+fn unreachable_posts_are_not_drift() {
+    let mut report = with_missing_images("popular_offline", 0);
+    for _ in 0..3 {
+        report.post_unreachable();
+    }
+    let report = recorded(report);
+    assert!(!report.degraded, "{:?}", report.problems);
+    assert_eq!(report.posts_unreachable, 3);
+
+    // Not one post of the digest loaded, its entries were still found.
+    let mut report = ScrapeReport::new("recently_updated_offline");
+    report.page_fetched();
+    report.post_unreachable();
+    assert!(!recorded(report).degraded);
+
+    let mut report = ScrapeReport::new("empty_listing");
+    report.page_fetched();
+    assert!(recorded(report).degraded);
+}
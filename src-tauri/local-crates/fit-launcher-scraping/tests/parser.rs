@@ -0,0 +1,218 @@
+use fit_launcher_scraping::parser::{
+    REPACK_POST_PARSER, is_newer_version, parse_repack_version, parse_size,
+};
+use fit_launcher_scraping::structs::{Mirror, MirrorKind, RepackPost};
+
+const GB: f64 = (1u64 << 30) as f64;
+
+fn load_post(name: &str, href: &str) -> RepackPost {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let body = std::fs::read_to_string(path).expect("fixture should exist");
+    REPACK_POST_PARSER.parse_post(&body, href)
+}
+
+fn gigabytes(bytes: u64) -> f64 {
+    (bytes as f64 / GB * 10.0).round() / 10.0
+}
+
+#[test]
+fn reads_the_post_header() {
+    let post = load_post(
+        "post_selective_download.html",
+        "https://fitgirl-repacks.site/elden-ring/",
+    );
+    let metadata = &post.metadata;
+
+    assert_eq!(metadata.repack_number, Some(4512));
+    assert_eq!(
+        metadata.genres,
+        ["Action", "RPG", "3D", "Open world"].map(String::from)
+    );
+    assert_eq!(
+        metadata.companies,
+        ["FromSoftware", "Bandai Namco Entertainment"].map(String::from)
+    );
+    assert_eq!(metadata.languages, ["ENG", "MULTI14"].map(String::from));
+    assert_eq!(
+        metadata.published_at.map(|date| date.to_rfc3339()),
+        Some("2024-12-20T18:30:12+03:00".to_string())
+    );
+    assert_eq!(metadata.version.as_deref(), Some("v1.16.1"));
+
+    let original_size = metadata.original_size.as_ref().unwrap();
+    assert_eq!(gigabytes(original_size.bytes), 136.3);
+    assert!(!original_size.minimum);
+
+    let repack_size = metadata.repack_size.as_ref().unwrap();
+    assert_eq!(repack_size.raw, "from 71.9 GB");
+    assert_eq!(gigabytes(repack_size.bytes), 71.9);
+    assert!(repack_size.minimum);
+}
+
+#[test]
+fn reads_labels_split_over_several_lines() {
+    let post = load_post(
+        "post_build_hotfix.html",
+        "https://fitgirl-repacks.site/satisfactory/",
+    );
+    let metadata = &post.metadata;
+
+    assert_eq!(post.href, "https://fitgirl-repacks.site/satisfactory/");
+    assert_eq!(metadata.repack_number, Some(3987));
+    assert_eq!(
+        metadata.genres,
+        ["Simulation", "Building", "3D"].map(String::from)
+    );
+    assert_eq!(
+        metadata.companies,
+        ["Coffee Stain Studios"].map(String::from)
+    );
+    assert_eq!(
+        metadata.languages,
+        ["RUS", "ENG", "MULTI9"].map(String::from)
+    );
+    assert_eq!(metadata.version.as_deref(), Some("Build 16282911"));
+
+    let repack_size = metadata.repack_size.as_ref().unwrap();
+    assert_eq!(gigabytes(repack_size.bytes), 1.5);
+    assert!(repack_size.minimum);
+    assert_eq!(
+        gigabytes(metadata.original_size.as_ref().unwrap().bytes),
+        21.4
+    );
+}
+
+#[test]
+fn parses_sizes() {
+    let size = parse_size("850 MB").unwrap();
+    assert_eq!(size.bytes, 850 << 20);
+    assert!(!size.minimum);
+
+    let size = parse_size("From 23.1 GB [Selective Download]").unwrap();
+    assert_eq!(gigabytes(size.bytes), 23.1);
+    assert!(size.minimum);
+
+    // A comma is a decimal separator, unless exactly three digits follow it.
+    assert_eq!(gigabytes(parse_size("from 1,5 GB").unwrap().bytes), 1.5);
+    assert_eq!(parse_size("1,234 MB").unwrap().bytes, 1234 << 20);
+    assert_eq!(parse_size("1,234.5 MB").unwrap().bytes, 2469 << 19);
+    assert_eq!(parse_size("2,048,000 KB").unwrap().bytes, 2_048_000 << 10);
+
+    assert_eq!(parse_size("1 TB").unwrap().bytes, 1 << 40);
+    assert!(parse_size("unknown").is_none());
+}
+
+#[test]
+fn reads_the_version_from_the_title() {
+    let cases = [
+        (
+            "Cyberpunk 2077: Ultimate Edition – v2.21 + All DLCs + Bonus Content",
+            Some("v2.21"),
+        ),
+        (
+            "Satisfactory – Build 16282911 + Hotfix 2",
+            Some("Build 16282911"),
+        ),
+        (
+            "Stalker 2 – v1.0.3-hotfix, + Bonus OST",
+            Some("v1.0.3-hotfix"),
+        ),
+        ("Lies of P – v1.7.0.0 (Build 12345)", Some("v1.7.0.0")),
+        ("Hollow Knight", None),
+    ];
+
+    for (title, version) in cases {
+        assert_eq!(parse_repack_version(title).as_deref(), version, "{}", title);
+    }
+}
+
+#[test]
+fn compares_versions() {
+    assert!(is_newer_version("v1.2.9", "v1.2.10"));
+    assert!(is_newer_version("v1.9.3", "v1.16.1"));
+    assert!(!is_newer_version("v1.16.1", "v1.9.3"));
+    assert!(!is_newer_version("V1.2", "v1.2"));
+
+    assert!(is_newer_version("Build 16282911", "Build 16300001"));
+    assert!(!is_newer_version("Build 16300001", "Build 16282911"));
+
+    // A hotfix of the installed version is an update, not the other way round.
+    assert!(is_newer_version("v1.0.3", "v1.0.3-hotfix"));
+    assert!(is_newer_version("v1.0.3-hotfix", "v1.0.3-hotfix2"));
+    assert!(!is_newer_version("v1.0.3-hotfix", "v1.0.3"));
+
+    // Switching from versions to build numbers can't be compared, it is reported.
+    assert!(is_newer_version("v1.2", "Build 100"));
+}
+
+fn mirror(kind: MirrorKind, host: &str, url: &str) -> Mirror {
+    Mirror {
+        kind,
+        host: host.to_string(),
+        url: url.to_string(),
+    }
+}
+
+#[test]
+fn classifies_download_mirrors() {
+    let post = load_post(
+        "post_selective_download.html",
+        "https://fitgirl-repacks.site/elden-ring/",
+    );
+    let magnet =
+        "magnet:?xt=urn:btih:9F2C6D58A1E0B5C3D7E4F6A8B0C2D4E6F8A0B2C4&dn=Elden+Ring+Deluxe+Edition";
+    let paste = "https://paste.fitgirl-repacks.site/?3f1c2e9ab04d7c55#kDVTwmUSuE1TtywrFnmjoJLaYHW77zT7CEuopwpTN7M";
+
+    // The magnet shared by both trackers is only listed once.
+    assert_eq!(
+        post.mirrors,
+        [
+            mirror(
+                MirrorKind::Torrent,
+                "1337x.to",
+                "https://1337x.to/torrent/6212345/Elden-Ring-Deluxe-Edition-FitGirl-Repack/",
+            ),
+            mirror(MirrorKind::Magnet, "magnet", magnet),
+            mirror(MirrorKind::TorrentFile, "paste.fitgirl-repacks.site", paste),
+            mirror(
+                MirrorKind::Torrent,
+                "rutor.info",
+                "https://rutor.info/torrent/1012345"
+            ),
+            mirror(
+                MirrorKind::DirectDownload,
+                "FuckingFast",
+                "https://fitgirl-repacks.site/elden-ring/#FuckingFast",
+            ),
+            mirror(
+                MirrorKind::DirectDownload,
+                "DataNodes",
+                "https://datanodes.to/download/elden-ring-deluxe",
+            ),
+        ]
+    );
+    assert_eq!(post.magnetlink, magnet);
+    assert_eq!(post.torrent_paste_link, paste);
+}
+
+#[test]
+fn keeps_mirrors_of_unknown_hosts() {
+    let post = load_post(
+        "post_build_hotfix.html",
+        "https://fitgirl-repacks.site/satisfactory/",
+    );
+
+    let kinds: Vec<MirrorKind> = post.mirrors.iter().map(|m| m.kind).collect();
+    assert_eq!(
+        kinds,
+        [MirrorKind::Torrent, MirrorKind::Magnet, MirrorKind::Other]
+    );
+    assert_eq!(
+        post.mirrors.last(),
+        Some(&mirror(
+            MirrorKind::Other,
+            "example-hoster.net",
+            "https://example-hoster.net/file/satisfactory-fitgirl",
+        ))
+    );
+}
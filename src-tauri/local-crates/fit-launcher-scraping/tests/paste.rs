@@ -0,0 +1,72 @@
+use fit_launcher_scraping::errors::PasteError;
+use fit_launcher_scraping::paste::{cipher_info_url, decrypt_paste, save_torrent};
+use fitgirl_decrypt::CipherInfo;
+
+const TORRENT_PASTE_LINK: &str = "https://paste.fitgirl-repacks.site/?3f1c2e9ab04d7c55#kDVTwmUSuE1TtywrFnmjoJLaYHW77zT7CEuopwpTN7M";
+const DDL_PASTE_LINK: &str = "https://paste.fitgirl-repacks.site/?9ad07b61c3e2f418#3qheuHCnn9S2nhjgNWuFkwDz9Tojz6GsRquFccLPch2r";
+
+fn load_fixture(name: &str) -> CipherInfo {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let content = std::fs::read_to_string(path).expect("fixture should exist");
+    serde_json::from_str(&content).expect("fixture should be a PrivateBin response")
+}
+
+#[test]
+fn decrypts_torrent_attachment() {
+    let decrypted = decrypt_paste(TORRENT_PASTE_LINK, load_fixture("torrent_paste.json")).unwrap();
+
+    assert_eq!(
+        decrypted.attachment_name,
+        "Example_Game_[FitGirl_Repack].torrent"
+    );
+    assert!(decrypted.torrent.unwrap().starts_with(b"d8:announce"));
+    assert!(decrypted.ddl_links.is_empty());
+}
+
+#[test]
+fn decrypts_ddl_link_list() {
+    let decrypted = decrypt_paste(DDL_PASTE_LINK, load_fixture("ddl_paste.json")).unwrap();
+
+    assert!(decrypted.torrent.is_none());
+    assert_eq!(decrypted.ddl_links.len(), 3);
+    assert!(
+        decrypted
+            .ddl_links
+            .iter()
+            .all(|link| link.starts_with("https://"))
+    );
+}
+
+#[test]
+fn rejects_paste_with_wrong_key() {
+    let result = decrypt_paste(DDL_PASTE_LINK, load_fixture("torrent_paste.json"));
+
+    assert!(matches!(result, Err(PasteError::Decrypt(_))));
+}
+
+#[test]
+fn saves_torrents_without_replacing_others() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let first = save_torrent(dir.path(), "../Game.torrent", b"d8:announce1e").unwrap();
+    assert_eq!(first, dir.path().join("Game.torrent"));
+
+    // The same torrent again is reused, another one with that name is put next to it.
+    let again = save_torrent(dir.path(), "Game.torrent", b"d8:announce1e").unwrap();
+    assert_eq!(again, first);
+    let other = save_torrent(dir.path(), "Game.torrent", b"d8:announce2e").unwrap();
+    assert_eq!(other, dir.path().join("Game (2).torrent"));
+
+    assert_eq!(std::fs::read(&first).unwrap(), b"d8:announce1e");
+    assert_eq!(std::fs::read(&other).unwrap(), b"d8:announce2e");
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+}
+
+#[test]
+fn requests_the_paste_by_its_id_only() {
+    assert_eq!(
+        cipher_info_url(TORRENT_PASTE_LINK).unwrap(),
+        "https://paste.fitgirl-repacks.site/?pasteid=3f1c2e9ab04d7c55"
+    );
+    assert!(cipher_info_url("https://paste.fitgirl-repacks.site/3f1c2e9ab04d7c55").is_err());
+}
@@ -0,0 +1,142 @@
+use fit_launcher_scraping::catalog::{Catalog, title_from_slug};
+use fit_launcher_scraping::search::{SearchFilters, SearchHit, SearchIndex};
+use fit_launcher_scraping::structs::{CatalogEntry, RepackMetadata, RepackSize};
+
+fn catalog(slugs: &[&str]) -> Catalog {
+    let mut catalog = Catalog::default();
+    for slug in slugs {
+        let url = format!("https://fitgirl-repacks.site/{}/", slug);
+        catalog.entries.insert(
+            url.clone(),
+            CatalogEntry {
+                url,
+                slug: slug.to_string(),
+                title: title_from_slug(slug),
+                lastmod: None,
+                image: None,
+            },
+        );
+    }
+    catalog
+}
+
+fn scraped(slug: &str, title: &str, genres: &[&str], repack_gigabytes: u64) -> SearchHit {
+    SearchHit {
+        id: slug.to_string(),
+        url: format!("https://fitgirl-repacks.site/{}/", slug),
+        title: title.to_string(),
+        image: Some(format!("https://i5.imageban.ru/out/{}.jpg", slug)),
+        metadata: Some(RepackMetadata {
+            genres: genres.iter().map(|genre| genre.to_string()).collect(),
+            repack_size: Some(RepackSize {
+                raw: format!("{} GB", repack_gigabytes),
+                bytes: repack_gigabytes << 30,
+                minimum: false,
+            }),
+            ..RepackMetadata::default()
+        }),
+        score: 0.0,
+    }
+}
+
+fn index() -> SearchIndex {
+    SearchIndex::build(
+        &catalog(&[
+            "cyberpunk-2077-ultimate-edition",
+            "hollow-knight",
+            "dead-cells",
+            "knights-of-the-old-republic",
+        ]),
+        vec![
+            scraped(
+                "cyberpunk-2077-ultimate-edition",
+                "Cyberpunk 2077: Ultimate Edition – v2.21 + All DLCs",
+                &["Action", "RPG", "Open world"],
+                60,
+            ),
+            scraped(
+                "dead-cells",
+                "Dead Cells – v35 + 7 DLCs",
+                &["Roguelike", "Action"],
+                1,
+            ),
+            scraped(
+                "disco-elysium",
+                "Disco Elysium – The Final Cut",
+                &["RPG"],
+                12,
+            ),
+        ],
+    )
+}
+
+fn titles(index: &SearchIndex, query: &str, filters: &SearchFilters) -> Vec<String> {
+    index
+        .search(query, filters, 0)
+        .hits
+        .into_iter()
+        .map(|hit| hit.title)
+        .collect()
+}
+
+#[test]
+fn merges_scraped_posts_into_the_catalog() {
+    let index = index();
+
+    // One entry per post, the scraped title replaces the one derived from the slug.
+    assert_eq!(index.len(), 5);
+    assert_eq!(
+        titles(&index, "cyberpunk", &SearchFilters::default()),
+        ["Cyberpunk 2077: Ultimate Edition – v2.21 + All DLCs"]
+    );
+}
+
+#[test]
+fn tolerates_typos_of_longer_words() {
+    let index = index();
+
+    assert_eq!(
+        titles(&index, "cyberpnuk", &SearchFilters::default()),
+        ["Cyberpunk 2077: Ultimate Edition – v2.21 + All DLCs"]
+    );
+    assert_eq!(
+        titles(&index, "holow knigt", &SearchFilters::default()),
+        ["Hollow Knight"]
+    );
+    // Short words must match exactly or as a prefix.
+    assert!(titles(&index, "rpq", &SearchFilters::default()).is_empty());
+}
+
+#[test]
+fn ranks_title_matches_above_genre_matches() {
+    let index = index();
+
+    // "Knights..." only has a prefix match, the exact title word wins.
+    assert_eq!(
+        titles(&index, "knight", &SearchFilters::default()),
+        ["Hollow Knight", "Knights Of The Old Republic"]
+    );
+    // Disco Elysium only has RPG as a genre, Cyberpunk too, then alphabetical.
+    assert_eq!(
+        titles(&index, "rpg", &SearchFilters::default()),
+        [
+            "Cyberpunk 2077: Ultimate Edition – v2.21 + All DLCs",
+            "Disco Elysium – The Final Cut",
+        ]
+    );
+    // Every query word has to match something.
+    assert!(titles(&index, "dead knight", &SearchFilters::default()).is_empty());
+}
+
+#[test]
+fn filters_on_scraped_metadata() {
+    let index = index();
+    let filters = SearchFilters {
+        genres: vec!["action".to_string()],
+        max_repack_size: Some(10 << 30),
+        ..SearchFilters::default()
+    };
+
+    // Catalog-only posts can't be checked against filters, they are left out.
+    assert_eq!(titles(&index, "", &filters), ["Dead Cells – v35 + 7 DLCs"]);
+}
@@ -0,0 +1,130 @@
+use fit_launcher_scraping::store::{GameStore, StoredFeed};
+use fit_launcher_scraping::structs::Game;
+use rusqlite::Connection;
+
+/// `games.db` as the first version of the store left it, before paste links.
+const SCHEMA_V1: &str = r#"
+CREATE TABLE posts (
+    id TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    image TEXT NOT NULL,
+    description TEXT NOT NULL,
+    magnetlink TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    metadata TEXT NOT NULL,
+    mirrors TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE feeds (
+    feed TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    post_id TEXT NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    update_entry TEXT,
+    PRIMARY KEY (feed, position)
+);
+CREATE TABLE images (
+    post_id TEXT NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    PRIMARY KEY (post_id, position)
+);
+CREATE TABLE colors (
+    image_url TEXT PRIMARY KEY,
+    colors TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+INSERT INTO posts VALUES ('elden-ring', 'https://fitgirl-repacks.site/elden-ring/',
+    'Elden Ring', 'https://i5.imageban.ru/elden-ring.jpg', 'An action RPG.', 'magnet:?xt=urn:btih:1',
+    'Action, RPG', '{"repack_number":null,"genres":[],"companies":[],"languages":[],
+    "original_size":null,"repack_size":null,"published_at":null,"version":null}',
+    '[]', 1734700000);
+INSERT INTO feeds VALUES ('discovery', 0, 'elden-ring', NULL);
+INSERT INTO images VALUES ('elden-ring', 0, 'https://i5.imageban.ru/elden-ring-1.jpg');
+PRAGMA user_version = 1;
+"#;
+
+#[test]
+fn upgrades_a_first_version_database() {
+    let data_dir = tempfile::tempdir().unwrap();
+    Connection::open(data_dir.path().join("games.db"))
+        .unwrap()
+        .execute_batch(SCHEMA_V1)
+        .unwrap();
+
+    let store = GameStore::open(data_dir.path().to_path_buf()).unwrap();
+
+    // The stored feed reads back, with the columns added since.
+    let pages = store.discovery_pages().unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].game_title, "Elden Ring");
+    assert_eq!(pages[0].game_torrent_paste_link, "");
+    assert_eq!(
+        pages[0].game_secondary_images,
+        ["https://i5.imageban.ru/elden-ring-1.jpg"]
+    );
+
+    // The tables added since are there.
+    store.mark_refreshed(StoredFeed::Discovery).unwrap();
+    assert!(store.refreshed_at(StoredFeed::Discovery).unwrap().is_some());
+    store
+        .set_image_variant("https://i5.imageban.ru/thumbs/1.jpg", None)
+        .unwrap();
+    assert!(
+        store
+            .image_variant("https://i5.imageban.ru/thumbs/1.jpg")
+            .unwrap()
+            .is_some()
+    );
+    drop(store);
+
+    // Opening it again finds it up to date.
+    let version: i32 = Connection::open(data_dir.path().join("games.db"))
+        .unwrap()
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, 4);
+    GameStore::open(data_dir.path().to_path_buf()).unwrap();
+}
+
+fn game(title: &str, img: &str) -> Game {
+    Game {
+        id: "elden-ring".to_string(),
+        title: title.to_string(),
+        img: img.to_string(),
+        desc: "An action RPG.".to_string(),
+        magnetlink: "magnet:?xt=urn:btih:1".to_string(),
+        href: "https://fitgirl-repacks.site/elden-ring/".to_string(),
+        tag: "Action, RPG".to_string(),
+        metadata: Default::default(),
+        mirrors: Vec::new(),
+        update: None,
+    }
+}
+
+#[test]
+fn popular_lists_keep_their_own_title_and_picture() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let store = GameStore::open(data_dir.path().to_path_buf()).unwrap();
+
+    let post = game(
+        "Elden Ring: Deluxe Edition – v1.16.0",
+        "https://i5.imageban.ru/elden-ring.jpg",
+    );
+    let popular = game("Elden Ring", "https://i5.imageban.ru/elden-ring-1.jpg");
+    store.replace_feed(StoredFeed::NewlyAdded, &[post]).unwrap();
+    store
+        .replace_feed(StoredFeed::PopularMonth, &[popular])
+        .unwrap();
+
+    let stored = store.game("elden-ring").unwrap().unwrap();
+    assert_eq!(stored.title, "Elden Ring: Deluxe Edition – v1.16.0");
+    assert_eq!(stored.img, "https://i5.imageban.ru/elden-ring.jpg");
+
+    let newly_added = &store.feed_games(StoredFeed::NewlyAdded).unwrap()[0];
+    assert_eq!(newly_added.title, "Elden Ring: Deluxe Edition – v1.16.0");
+
+    let popular = &store.feed_games(StoredFeed::PopularMonth).unwrap()[0];
+    assert_eq!(popular.title, "Elden Ring");
+    assert_eq!(popular.img, "https://i5.imageban.ru/elden-ring-1.jpg");
+}
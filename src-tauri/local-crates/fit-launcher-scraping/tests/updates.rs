@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chrono::DateTime;
+use fit_launcher_scraping::catalog::Catalog;
+use fit_launcher_scraping::errors::ScrapingError;
+use fit_launcher_scraping::structs::CatalogEntry;
+use fit_launcher_scraping::updates::outdated_library_games;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "{}/tests/fixtures/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    ))
+}
+
+#[tokio::test]
+async fn reports_installed_games_with_a_newer_repack() {
+    let checks = tempfile::tempdir().unwrap();
+    let outdated = outdated_library_games(
+        &fixture("downloaded_games.json"),
+        &fixture("updates_digest.json"),
+        &checks.path().join("catalog.json"),
+        &checks.path().join("post_checks.json"),
+        // Every installed game is in the digest, no post has to be read.
+        async |post_url: &str| -> Result<Option<String>, ScrapingError> {
+            panic!("{post_url} was fetched")
+        },
+    )
+    .await;
+
+    let versions: Vec<(&str, &str, &str)> = outdated
+        .iter()
+        .map(|game| {
+            (
+                game.post_url.as_str(),
+                game.installed_version.as_str(),
+                game.latest_version.as_str(),
+            )
+        })
+        .collect();
+
+    // Hollow Knight is up to date and the local game has no version to compare.
+    assert_eq!(
+        versions,
+        [
+            (
+                "https://fitgirl-repacks.site/elden-ring/",
+                "v1.16.0",
+                "v1.16.1"
+            ),
+            // Downloaded without a recorded version, read from its title.
+            (
+                "https://fitgirl-repacks.site/satisfactory/",
+                "Build 16282911",
+                "Build 16300001"
+            ),
+            (
+                "https://fitgirl-repacks.site/stalker-2-heart-of-chornobyl/",
+                "v1.0.3",
+                "v1.0.3-hotfix"
+            ),
+        ]
+    );
+    assert!(outdated[0].update.as_ref().unwrap().incremental_update);
+}
+
+fn save_catalog(path: &Path, elden_ring_lastmod: &str) {
+    let url = "https://fitgirl-repacks.site/elden-ring/".to_string();
+    let mut catalog = Catalog::default();
+    catalog.entries.insert(
+        url.clone(),
+        CatalogEntry {
+            url,
+            slug: "elden-ring".to_string(),
+            title: "Elden Ring".to_string(),
+            lastmod: Some(DateTime::parse_from_rfc3339(elden_ring_lastmod).unwrap()),
+            image: None,
+        },
+    );
+    catalog.save(path).unwrap();
+}
+
+#[tokio::test]
+async fn reads_posts_missing_from_the_digest_once_per_lastmod() {
+    let dir = tempfile::tempdir().unwrap();
+    let catalog_path = dir.path().join("catalog.json");
+    let checks_path = dir.path().join("post_checks.json");
+    let fetched = AtomicUsize::new(0);
+
+    let check = async || {
+        outdated_library_games(
+            &fixture("downloaded_games.json"),
+            &dir.path().join("updates_digest.json"),
+            &catalog_path,
+            &checks_path,
+            async |post_url: &str| -> Result<Option<String>, ScrapingError> {
+                fetched.fetch_add(1, Ordering::Relaxed);
+                Ok(post_url
+                    .contains("elden-ring")
+                    .then(|| "v1.16.2".to_string()))
+            },
+        )
+        .await
+        .into_iter()
+        .map(|game| (game.post_url, game.latest_version))
+        .collect::<Vec<(String, String)>>()
+    };
+    let elden_ring_update = [(
+        "https://fitgirl-repacks.site/elden-ring/".to_string(),
+        "v1.16.2".to_string(),
+    )];
+
+    save_catalog(&catalog_path, "2024-12-20T18:30:12+03:00");
+    assert_eq!(check().await, elden_ring_update);
+    // Elden Ring, Satisfactory, S.T.A.L.K.E.R. 2 and Hollow Knight.
+    assert_eq!(fetched.swap(0, Ordering::Relaxed), 4);
+
+    // Nothing moved, the versions found last time are reused.
+    assert_eq!(check().await, elden_ring_update);
+    assert_eq!(fetched.swap(0, Ordering::Relaxed), 0);
+
+    save_catalog(&catalog_path, "2024-12-27T10:00:00+03:00");
+    assert_eq!(check().await, elden_ring_update);
+    assert_eq!(fetched.load(Ordering::Relaxed), 1);
+}
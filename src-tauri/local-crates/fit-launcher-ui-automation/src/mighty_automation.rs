@@ -127,7 +127,7 @@ pub mod windows_ui_automation {
     pub fn start_executable_components_args(path: PathBuf) {
         #[cfg(target_os = "windows")]
         {
-            let installation_settings = get_installation_settings();
+            let installation_settings = get_installation_settings().unwrap_or_default();
             let mut checkboxes_list: Vec<String> = Vec::new();
 
             if installation_settings.directx_install {
@@ -175,7 +175,7 @@ pub mod windows_ui_automation {
         // Skip Select Setup Language.
         windows_controls_processes::click_ok_button();
         // Skip Select Setup Language.
-        let should_two_gb_limit = get_installation_settings().two_gb_limit;
+        let should_two_gb_limit = get_installation_settings().unwrap_or_default().two_gb_limit;
         if should_two_gb_limit {
             // Skip until checkboxes.
             thread::sleep(time::Duration::from_millis(1000));
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use fit_launcher_config::client::connectivity::{Connectivity, CONNECTIVITY};
+use fit_launcher_scraping::source::repack_source;
+use tauri::Emitter;
+use tracing::{error, info};
+
+use crate::scheduler::{run_feed_refresh, wake_scheduler, Feed};
+use crate::utils::Payload;
+
+/// How often the site is probed while it answers.
+const ONLINE_PROBE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// How often the site is probed while it doesn't, to notice it coming back.
+const OFFLINE_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+/// Failing requests can ask for a probe, never more often than this.
+const MIN_PROBE_GAP: Duration = Duration::from_secs(15);
+
+pub fn emit_network_failure(app_handle: &tauri::AppHandle) {
+    let failure_message = Payload {
+        message: "There was a network issue, unable to retrieve latest game data. (E01)"
+            .to_string(),
+    };
+    if let Err(e) = app_handle.emit("network-failure", failure_message) {
+        error!("Failed to emit network-failure: {}", e);
+    }
+}
+
+/// Probe the repack site and announce the new state if it changed.
+pub async fn check_connectivity(app_handle: &tauri::AppHandle) -> Connectivity {
+    let base_url = repack_source().base_url().to_string();
+
+    if let Some(state) = CONNECTIVITY.probe(&base_url).await {
+        if let Err(e) = app_handle.emit("connectivity-changed", state) {
+            error!("Failed to emit connectivity-changed: {}", e);
+        }
+        if state.is_offline() {
+            emit_network_failure(app_handle);
+        }
+    }
+
+    CONNECTIVITY.state()
+}
+
+/// Scrape every feed at once, feeds are skipped while offline.
+pub async fn refresh_all_feeds(app_handle: tauri::AppHandle) {
+    let tasks = Feed::ALL.map(|feed| run_feed_refresh(feed, app_handle.clone()));
+    futures::future::join_all(tasks).await;
+}
+
+/// Keep probing the site for as long as the app runs.
+///
+/// The scheduler refreshes every feed as soon as the site answers again.
+pub fn start_connectivity_watch(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let interval = if CONNECTIVITY.is_offline() {
+                OFFLINE_PROBE_INTERVAL
+            } else {
+                ONLINE_PROBE_INTERVAL
+            };
+            CONNECTIVITY.wait_for_suspicion(interval).await;
+
+            let was_offline = CONNECTIVITY.is_offline();
+            let state = check_connectivity(&app_handle).await;
+            if was_offline && !state.is_offline() {
+                info!("The site answers again, refreshing every feed");
+                wake_scheduler();
+            }
+
+            tokio::time::sleep(MIN_PROBE_GAP).await;
+        }
+    });
+}
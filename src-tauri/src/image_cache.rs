@@ -0,0 +1,314 @@
+//! Every cache of the game pictures, behind one service.
+//!
+//! From the closest to the farthest layer:
+//! - the screenshot links of the last viewed games, in memory and in
+//!   `image_cache.json` under the app cache directory, which the game page reads;
+//! - the pictures themselves and their thumbnails, in the [`IMAGE_STORE`];
+//! - the palettes and resolved variants, in the game store;
+//! - the game pages the links were scraped from, in the HTTP cache.
+//!
+//! Capacities are read from the gamehub settings once and applied again
+//! whenever the settings are saved, a change takes effect without restarting.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use directories::BaseDirs;
+use fit_launcher_config::client::cache::HTTP_CACHE;
+use fit_launcher_config::settings::config::{get_gamehub_settings, watch_gamehub_settings};
+use fit_launcher_config::settings::creation::ImageCacheSettings;
+use fit_launcher_scraping::errors::ScrapingError;
+use fit_launcher_scraping::store::GAME_STORE;
+use lru::LruCache;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use tempfile::NamedTempFile;
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
+use tracing::{info, warn};
+
+use crate::image_store::{ImageStoreReport, IMAGE_STORE};
+
+pub const IMAGE_CACHE_FILE_NAME: &str = "image_cache.json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImageCacheError {
+    #[error("Failed to access the image cache: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to write the image cache: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    GameStore(#[from] ScrapingError),
+}
+
+impl Serialize for ImageCacheError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageCacheStats {
+    /// Games whose screenshot links are remembered.
+    pub games: usize,
+    pub games_capacity: usize,
+    pub screenshot_links: usize,
+    pub links_file_bytes: u64,
+    pub store: ImageStoreReport,
+    pub http_cache_bytes: u64,
+    /// Everything above on disk.
+    pub total_bytes: u64,
+}
+
+pub struct ImageCacheService {
+    file_path: PathBuf,
+    /// Loaded from `file_path` on first use.
+    games: Mutex<Option<LruCache<String, Vec<String>>>>,
+    games_capacity: AtomicUsize,
+}
+
+fn configured_limits() -> ImageCacheSettings {
+    get_gamehub_settings()
+        .map(|settings| settings.image_cache)
+        .unwrap_or_else(|e| {
+            warn!(
+                "Failed to read the image cache settings, using the defaults: {}",
+                e
+            );
+            ImageCacheSettings::default()
+        })
+}
+
+/// Where older versions kept the links, they are only ever deleted now.
+fn legacy_files() -> Vec<PathBuf> {
+    let Some(base_dirs) = BaseDirs::new() else {
+        return Vec::new();
+    };
+
+    [base_dirs.config_dir(), base_dirs.config_local_dir()]
+        .into_iter()
+        .map(|dir| {
+            dir.join("com.fitlauncher.carrotrub")
+                .join(IMAGE_CACHE_FILE_NAME)
+        })
+        .collect()
+}
+
+async fn remove_if_exists(path: &std::path::Path) -> std::io::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+impl ImageCacheService {
+    /// `cache_dir` is the app cache directory, the links file is written in it.
+    pub fn new(cache_dir: PathBuf) -> Self {
+        let service = ImageCacheService {
+            file_path: cache_dir.join(IMAGE_CACHE_FILE_NAME),
+            games: Mutex::new(None),
+            games_capacity: AtomicUsize::new(0),
+        };
+        service.apply_limits(&configured_limits());
+        service
+    }
+
+    /// Use `limits` from now on, the links are resized on their next access.
+    pub fn apply_limits(&self, limits: &ImageCacheSettings) {
+        IMAGE_STORE.set_max_bytes(limits.store_max_megabytes * 1024 * 1024);
+        self.games_capacity
+            .store(limits.games_capacity, Ordering::Relaxed);
+    }
+
+    /// The links, loaded and resized to the configured capacity.
+    async fn games(&self) -> MappedMutexGuard<'_, LruCache<String, Vec<String>>> {
+        let capacity = NonZeroUsize::new(self.games_capacity.load(Ordering::Relaxed))
+            .unwrap_or(NonZeroUsize::MIN);
+
+        let mut guard = self.games.lock().await;
+        match guard.as_mut() {
+            Some(games) => games.resize(capacity),
+            None => {
+                let mut games = LruCache::new(capacity);
+                if let Ok(data) = tokio::fs::read_to_string(&self.file_path).await {
+                    match serde_json::from_str::<HashMap<String, Vec<String>>>(&data) {
+                        Ok(stored) => {
+                            for (game_link, images) in stored {
+                                games.put(game_link, images);
+                            }
+                        }
+                        Err(e) => warn!("Image cache file is corrupted, starting over: {}", e),
+                    }
+                }
+                *guard = Some(games);
+            }
+        }
+
+        MutexGuard::map(guard, |games| {
+            games.get_or_insert_with(|| LruCache::new(capacity))
+        })
+    }
+
+    async fn save(&self, games: &LruCache<String, Vec<String>>) -> Result<(), ImageCacheError> {
+        let stored: HashMap<&String, &Vec<String>> = games.iter().collect();
+        let data = serde_json::to_string_pretty(&stored)?;
+
+        let parent = self
+            .file_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+        tokio::fs::create_dir_all(&parent).await?;
+
+        // A temp file of its own, another writer can't move it half-written.
+        let path = self.file_path.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut temp_file = NamedTempFile::new_in(parent)?;
+            temp_file.write_all(data.as_bytes())?;
+            temp_file.persist(path).map_err(|e| e.error)?;
+            Ok::<_, std::io::Error>(())
+        })
+        .await
+        .map_err(std::io::Error::other)??;
+        Ok(())
+    }
+
+    /// The screenshot links of `game_link`, `None` unless some were found.
+    pub async fn images(&self, game_link: &str) -> Option<Vec<String>> {
+        self.games()
+            .await
+            .get(game_link)
+            .filter(|images| !images.is_empty())
+            .cloned()
+    }
+
+    pub async fn insert(
+        &self,
+        game_link: String,
+        images: Vec<String>,
+    ) -> Result<(), ImageCacheError> {
+        let mut games = self.games().await;
+        games.put(game_link, images);
+        self.save(&games).await
+    }
+
+    /// Forget everything cached for `game_link`, in every layer.
+    pub async fn evict_game(&self, game_link: &str) -> Result<(), ImageCacheError> {
+        let mut games = self.games().await;
+        let images = games.pop(game_link).unwrap_or_default();
+        self.save(&games).await?;
+        drop(games);
+
+        IMAGE_STORE.forget_urls(&images).await?;
+        GAME_STORE.forget_images(&images)?;
+        HTTP_CACHE.remove(game_link).await?;
+
+        info!("Evicted {} cached pictures of {}", images.len(), game_link);
+        Ok(())
+    }
+
+    /// Empty every layer, and the files older versions left behind.
+    ///
+    /// Only the game pages of the remembered games leave the HTTP cache, the
+    /// scraper's other responses are kept.
+    pub async fn clear(&self) -> Result<(), ImageCacheError> {
+        let mut games = self.games().await;
+        let game_links: Vec<String> = games
+            .iter()
+            .map(|(game_link, _)| game_link.clone())
+            .collect();
+        games.clear();
+        remove_if_exists(&self.file_path).await?;
+        drop(games);
+
+        for legacy_file in legacy_files() {
+            remove_if_exists(&legacy_file).await?;
+        }
+
+        IMAGE_STORE.clear().await?;
+        GAME_STORE.clear_images()?;
+        for game_link in &game_links {
+            HTTP_CACHE.remove(game_link).await?;
+        }
+
+        info!("Every image cache cleared");
+        Ok(())
+    }
+
+    pub async fn stats(&self) -> ImageCacheStats {
+        let games = self.games().await;
+        let games_len = games.len();
+        let games_capacity = games.cap().get();
+        let screenshot_links = games.iter().map(|(_, images)| images.len()).sum();
+        drop(games);
+
+        let links_file_bytes = tokio::fs::metadata(&self.file_path)
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        let store = IMAGE_STORE.report().await;
+        let http_cache_bytes = HTTP_CACHE.report().await.total_bytes;
+
+        ImageCacheStats {
+            games: games_len,
+            games_capacity,
+            screenshot_links,
+            links_file_bytes,
+            total_bytes: links_file_bytes + store.total_bytes + http_cache_bytes,
+            store,
+            http_cache_bytes,
+        }
+    }
+}
+
+/// Apply the image cache limits of every gamehub settings saved from now on.
+pub fn follow_image_cache_settings(app_handle: AppHandle) {
+    let mut saved_settings = watch_gamehub_settings();
+
+    tauri::async_runtime::spawn(async move {
+        while saved_settings.changed().await.is_ok() {
+            let limits = saved_settings
+                .borrow_and_update()
+                .as_ref()
+                .map(|settings| settings.image_cache.clone());
+            if let Some(limits) = limits {
+                app_handle
+                    .state::<ImageCacheService>()
+                    .apply_limits(&limits);
+            }
+        }
+    });
+}
+
+/// Entries and sizes of every image cache layer.
+#[tauri::command]
+pub async fn get_image_cache_stats(
+    image_cache: State<'_, ImageCacheService>,
+) -> Result<ImageCacheStats, ImageCacheError> {
+    Ok(image_cache.stats().await)
+}
+
+/// Forget the screenshots of one game, they are fetched again when its page is opened.
+#[tauri::command]
+pub async fn evict_game_images(
+    image_cache: State<'_, ImageCacheService>,
+    game_link: String,
+) -> Result<(), ImageCacheError> {
+    image_cache.evict_game(&game_link).await
+}
+
+#[tauri::command]
+pub async fn clear_all_cache(
+    image_cache: State<'_, ImageCacheService>,
+) -> Result<(), ImageCacheError> {
+    image_cache.clear().await
+}
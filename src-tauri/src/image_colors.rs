@@ -1,76 +1,379 @@
+//! Colors of the game pictures, the pages showing them are tinted with these.
+//!
+//! Pictures go through the image store, so any format the `image` crate
+//! decodes works and the palette of a picture is only ever extracted once: it
+//! is kept in the game store, keyed by the picture URL.
+
 use color_thief::{get_palette, ColorFormat};
-use image::{load_from_memory, DynamicImage, ImageError};
+use fit_launcher_scraping::store::GAME_STORE;
+use futures::{stream, StreamExt};
+use image::{load_from_memory, ImageError};
+use palette::{color_difference::Wcag21RelativeContrast, FromColor, Hsl, Srgb};
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
 use thiserror::Error;
+use tracing::warn;
+
+use crate::image_store::{ImageStoreError, IMAGE_STORE};
+
+/// Pictures are shrunk to fit this before being analyzed, the colors stay the same.
+const ANALYSIS_SIZE: u32 = 256;
+/// Colors the picture is quantized to, the swatches are picked among them.
+const PALETTE_SIZE: u8 = 8;
+/// One pixel out of this many is sampled.
+const SAMPLING: u8 = 5;
+/// WCAG 2.1 AA contrast for normal text.
+const MIN_TEXT_CONTRAST: f32 = 4.5;
+/// Pictures of a batch downloaded and analyzed at once.
+const MAX_CONCURRENT_EXTRACTIONS: usize = 4;
 
 #[derive(Debug, Error)]
-enum DominantColorError {
-    #[error("Failed to download image from URL: {0}")]
-    Network(reqwest::Error),
+pub enum PaletteError {
+    #[error(transparent)]
+    Store(#[from] ImageStoreError),
     #[error("Failed to decode image data: {0}")]
-    ImageDecoding(ImageError),
-    #[error("Failed to extract dominant color")]
+    ImageDecoding(#[from] ImageError),
+    #[error("Failed to extract the colors of the image")]
     ColorExtraction,
-    #[error("Unexpected image format")]
-    Format,
 }
 
-impl From<reqwest::Error> for DominantColorError {
-    fn from(error: reqwest::Error) -> Self {
-        DominantColorError::Network(error)
+impl Serialize for PaletteError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
     }
 }
 
-impl From<ImageError> for DominantColorError {
-    fn from(error: ImageError) -> Self {
-        DominantColorError::ImageDecoding(error)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RgbColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HslColor {
+    /// Degrees, from 0 to 360.
+    pub h: f32,
+    /// From 0 to 1.
+    pub s: f32,
+    /// From 0 to 1.
+    pub l: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Swatch {
+    pub rgb: RgbColor,
+    pub hsl: HslColor,
+    /// `#rrggbb`, ready for CSS.
+    pub hex: String,
+}
+
+impl Swatch {
+    fn new(r: u8, g: u8, b: u8) -> Self {
+        let hsl = Hsl::from_color(Srgb::new(r, g, b).into_format::<f32>());
+
+        Swatch {
+            rgb: RgbColor { r, g, b },
+            hsl: HslColor {
+                h: hsl.hue.into_positive_degrees(),
+                s: hsl.saturation,
+                l: hsl.lightness,
+            },
+            hex: format!("#{:02x}{:02x}{:02x}", r, g, b),
+        }
+    }
+
+    fn srgb(&self) -> Srgb<f32> {
+        Srgb::new(self.rgb.r, self.rgb.g, self.rgb.b).into_format()
+    }
+
+    fn contrast_with(&self, other: &Swatch) -> f32 {
+        self.srgb().relative_contrast(other.srgb())
     }
 }
 
-async fn get_image_from_url(url: &str) -> Result<DynamicImage, DominantColorError> {
-    let response = reqwest::get(url)
-        .await
-        .map_err(DominantColorError::Network)?;
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(DominantColorError::Network)?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImagePalette {
+    /// The most common color.
+    pub dominant: Swatch,
+    /// A saturated color, if the picture has one.
+    pub vibrant: Option<Swatch>,
+    /// A desaturated color, if the picture has one.
+    pub muted: Option<Swatch>,
+    /// Readable over `dominant`, one of the picture colors when one is contrasted enough.
+    pub foreground: Swatch,
+    /// WCAG 2.1 contrast ratio between `foreground` and `dominant`.
+    pub contrast: f32,
+}
 
-    let img = load_from_memory(&bytes).map_err(DominantColorError::ImageDecoding)?;
-    Ok(img)
+/// What a vibrant or muted swatch looks like.
+struct SwatchTarget {
+    saturation: f32,
+    lightness: f32,
+    saturation_range: (f32, f32),
+    lightness_range: (f32, f32),
 }
 
-fn get_image_buffer(img: DynamicImage) -> Result<(Vec<u8>, ColorFormat), DominantColorError> {
-    match img {
-        DynamicImage::ImageRgb8(buffer) => Ok((buffer.to_vec(), ColorFormat::Rgb)),
-        DynamicImage::ImageRgba8(buffer) => Ok((buffer.to_vec(), ColorFormat::Rgba)),
-        _ => Err(DominantColorError::Format),
+const VIBRANT: SwatchTarget = SwatchTarget {
+    saturation: 1.0,
+    lightness: 0.5,
+    saturation_range: (0.35, 1.0),
+    lightness_range: (0.3, 0.7),
+};
+
+const MUTED: SwatchTarget = SwatchTarget {
+    saturation: 0.3,
+    lightness: 0.5,
+    saturation_range: (0.0, 0.4),
+    lightness_range: (0.3, 0.7),
+};
+
+impl SwatchTarget {
+    fn matches(&self, swatch: &Swatch) -> bool {
+        let (min_s, max_s) = self.saturation_range;
+        let (min_l, max_l) = self.lightness_range;
+        (min_s..=max_s).contains(&swatch.hsl.s) && (min_l..=max_l).contains(&swatch.hsl.l)
+    }
+
+    /// The closest matching swatch, `swatches` go from the most to the least common.
+    fn pick(&self, swatches: &[Swatch]) -> Option<Swatch> {
+        let distance = |rank: usize, swatch: &Swatch| {
+            (swatch.hsl.s - self.saturation).abs()
+                + (swatch.hsl.l - self.lightness).abs()
+                + rank as f32 * 0.05
+        };
+
+        swatches
+            .iter()
+            .enumerate()
+            .filter(|(_, swatch)| self.matches(swatch))
+            .min_by(|(a_rank, a), (b_rank, b)| {
+                distance(*a_rank, a).total_cmp(&distance(*b_rank, b))
+            })
+            .map(|(_, swatch)| swatch.clone())
     }
 }
 
-async fn fetch_dominant_color(url: &str) -> Result<String, DominantColorError> {
-    let result_image = get_image_from_url(url).await?; // Await here
-    let image_raw = get_image_buffer(result_image)?;
+/// The most common picture color readable over `background`, black or white otherwise.
+fn pick_foreground(swatches: &[Swatch], background: &Swatch) -> (Swatch, f32) {
+    let readable = swatches
+        .iter()
+        .map(|swatch| (swatch.clone(), swatch.contrast_with(background)))
+        .find(|(_, contrast)| *contrast >= MIN_TEXT_CONTRAST);
+    if let Some(readable) = readable {
+        return readable;
+    }
+
+    let white = Swatch::new(255, 255, 255);
+    let black = Swatch::new(0, 0, 0);
+    let (white_contrast, black_contrast) = (
+        white.contrast_with(background),
+        black.contrast_with(background),
+    );
+    if white_contrast >= black_contrast {
+        (white, white_contrast)
+    } else {
+        (black, black_contrast)
+    }
+}
+
+fn extract_palette(bytes: &[u8]) -> Result<ImagePalette, PaletteError> {
+    // Every format is converted, color_thief only reads 8 bit pixels.
+    let pixels = load_from_memory(bytes)?
+        .thumbnail(ANALYSIS_SIZE, ANALYSIS_SIZE)
+        .to_rgb8();
+
+    let swatches: Vec<Swatch> =
+        get_palette(pixels.as_raw(), ColorFormat::Rgb, SAMPLING, PALETTE_SIZE)
+            .map_err(|_| PaletteError::ColorExtraction)?
+            .iter()
+            .map(|color| Swatch::new(color.r, color.g, color.b))
+            .collect();
+
+    let dominant = swatches
+        .first()
+        .cloned()
+        .ok_or(PaletteError::ColorExtraction)?;
+    let (foreground, contrast) = pick_foreground(&swatches, &dominant);
+
+    Ok(ImagePalette {
+        vibrant: VIBRANT.pick(&swatches),
+        muted: MUTED.pick(&swatches),
+        dominant,
+        foreground,
+        contrast,
+    })
+}
+
+/// The palette of the picture at `url`, extracted on first use.
+pub async fn image_palette(url: &str) -> Result<ImagePalette, PaletteError> {
+    match GAME_STORE.colors(url) {
+        Ok(Some(stored)) => match serde_json::from_str(&stored) {
+            Ok(palette) => return Ok(palette),
+            // Stored by an older version, extracted again below.
+            Err(e) => warn!("Stored palette of {} is unreadable: {}", url, e),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("Failed to read the stored palette of {}: {}", url, e),
+    }
+
+    let hash = IMAGE_STORE.store_url(url).await?;
+    let (bytes, _) = IMAGE_STORE
+        .read(&hash)
+        .await
+        .ok_or_else(|| ImageStoreError::NotStored(hash))?;
 
-    let color_rgb = get_palette(&image_raw.0, image_raw.1, 10, 2)
-        .map_err(|_| DominantColorError::ColorExtraction)?;
+    let palette = tokio::task::spawn_blocking(move || extract_palette(&bytes))
+        .await
+        .map_err(|_| PaletteError::ColorExtraction)??;
 
-    let dominant_color = color_rgb[0];
-    Ok(format!(
-        "({}, {}, {})",
-        dominant_color.r, dominant_color.g, dominant_color.b
-    ))
+    match serde_json::to_string(&palette) {
+        Ok(stored) => {
+            if let Err(e) = GAME_STORE.set_colors(url, &stored) {
+                warn!("Failed to store the palette of {}: {}", url, e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize the palette of {}: {}", url, e),
+    }
+
+    Ok(palette)
 }
 
+/// Dominant, vibrant and muted colors of the picture at `url`, with a readable foreground.
 #[tauri::command]
-pub async fn check_dominant_color_vec(list_images: Vec<String>) -> Result<Vec<String>, String> {
-    let mut rgb_color_list: Vec<String> = Vec::new();
+pub async fn get_image_palette(url: String) -> Result<ImagePalette, PaletteError> {
+    image_palette(&url).await
+}
+
+/// One picture of a [`check_dominant_color_vec`] batch, also emitted as `dominant-color-extracted`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractedColor {
+    /// Position of the picture in the batch.
+    pub index: usize,
+    pub url: String,
+    /// `(r, g, b)` of the dominant color, `None` if it couldn't be extracted.
+    pub color: Option<String>,
+    pub error: Option<String>,
+}
 
-    for link in list_images {
-        match fetch_dominant_color(&link).await {
-            Ok(rgb_value) => rgb_color_list.push(rgb_value),
-            Err(e) => return Err(format!("Failed to fetch color for {}: {}", link, e)),
+impl ExtractedColor {
+    fn new(index: usize, url: String, palette: Result<ImagePalette, PaletteError>) -> Self {
+        let (color, error) = match palette {
+            Ok(palette) => {
+                let RgbColor { r, g, b } = palette.dominant.rgb;
+                (Some(format!("({}, {}, {})", r, g, b)), None)
+            }
+            Err(e) => {
+                warn!("Failed to fetch color for {}: {}", url, e);
+                (None, Some(e.to_string()))
+            }
+        };
+
+        ExtractedColor {
+            index,
+            url,
+            color,
+            error,
         }
     }
+}
+
+/// Dominant color of every picture, a picture failing doesn't fail the others.
+///
+/// Each result is emitted as soon as it is known, the whole batch is returned
+/// in the order of `list_images` once done.
+#[tauri::command]
+pub async fn check_dominant_color_vec(
+    app_handle: tauri::AppHandle,
+    list_images: Vec<String>,
+) -> Vec<ExtractedColor> {
+    let mut extracted: Vec<ExtractedColor> = stream::iter(list_images.into_iter().enumerate())
+        .map(|(index, url)| async move {
+            let palette = image_palette(&url).await;
+            ExtractedColor::new(index, url, palette)
+        })
+        .buffer_unordered(MAX_CONCURRENT_EXTRACTIONS)
+        .inspect(|color| {
+            if let Err(e) = app_handle.emit("dominant-color-extracted", color) {
+                warn!("Failed to emit dominant-color-extracted: {}", e);
+            }
+        })
+        .collect()
+        .await;
+
+    extracted.sort_by_key(|color| color.index);
+    extracted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swatches(colors: &[(u8, u8, u8)]) -> Vec<Swatch> {
+        colors
+            .iter()
+            .map(|&(r, g, b)| Swatch::new(r, g, b))
+            .collect()
+    }
+
+    fn hex(swatch: Option<Swatch>) -> Option<String> {
+        swatch.map(|swatch| swatch.hex)
+    }
+
+    #[test]
+    fn picks_the_swatches_matching_each_target() {
+        let swatches = swatches(&[
+            (20, 20, 20),
+            (120, 110, 100),
+            (230, 30, 30),
+            (250, 250, 250),
+        ]);
+
+        assert_eq!(hex(VIBRANT.pick(&swatches)).as_deref(), Some("#e61e1e"));
+        assert_eq!(hex(MUTED.pick(&swatches)).as_deref(), Some("#786e64"));
+    }
+
+    #[test]
+    fn prefers_the_closest_then_the_most_common_swatch() {
+        // A dull brown is vibrant enough but further from the target than red.
+        let closest = swatches(&[(150, 100, 60), (230, 30, 30)]);
+        assert_eq!(hex(VIBRANT.pick(&closest)).as_deref(), Some("#e61e1e"));
+
+        // Blue and red are as close, blue is more common.
+        let as_close = swatches(&[(30, 30, 230), (230, 30, 30)]);
+        assert_eq!(hex(VIBRANT.pick(&as_close)).as_deref(), Some("#1e1ee6"));
+    }
 
-    Ok(rgb_color_list)
+    #[test]
+    fn picks_nothing_when_no_swatch_matches() {
+        let swatches = swatches(&[(20, 20, 20), (250, 250, 250)]);
+
+        assert!(VIBRANT.pick(&swatches).is_none());
+        assert!(MUTED.pick(&swatches).is_none());
+    }
+
+    #[test]
+    fn picks_the_most_common_readable_picture_color() {
+        let swatches = swatches(&[(10, 10, 10), (90, 90, 90), (220, 220, 220), (255, 255, 255)]);
+
+        let (foreground, contrast) = pick_foreground(&swatches, &swatches[0]);
+        assert_eq!(foreground.hex, "#dcdcdc");
+        assert!(contrast >= MIN_TEXT_CONTRAST);
+    }
+
+    #[test]
+    fn falls_back_to_black_or_white_when_no_picture_color_is_readable() {
+        let gray = swatches(&[(128, 128, 128), (100, 100, 100), (150, 150, 150)]);
+        let (foreground, contrast) = pick_foreground(&gray, &gray[0]);
+        assert_eq!(foreground.hex, "#000000");
+        assert!(contrast >= MIN_TEXT_CONTRAST);
+
+        let navy = swatches(&[(20, 20, 120), (60, 60, 150)]);
+        let (foreground, contrast) = pick_foreground(&navy, &navy[0]);
+        assert_eq!(foreground.hex, "#ffffff");
+        assert!(contrast >= MIN_TEXT_CONTRAST);
+    }
 }
@@ -0,0 +1,832 @@
+//! Content-addressed store of the pictures shown by the launcher.
+//!
+//! Images are downloaded once, kept on disk under the SHA-256 of their bytes
+//! and served to the webview through the `fitimg` protocol:
+//! `fitimg://localhost/<hash>`, `https://fitimg.localhost/<hash>` on Windows.
+//! `fitimg://localhost/?url=<encoded url>` serves a picture by its source URL
+//! and downloads it on first use, only from [`IMAGE_HOSTS`] or the repack site
+//! and up to [`MAX_IMAGE_BYTES`]. Past the configured size, by default
+//! [`IMAGE_STORE_MAX_BYTES`], the least recently used pictures are evicted.
+//!
+//! Next to each picture WebP thumbnails are kept at [`THUMBNAIL_WIDTHS`],
+//! `?w=<width>` serves the smallest one at least that wide, along with a tiny
+//! blurred placeholder to show while it loads.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Cursor, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use directories::BaseDirs;
+use fit_launcher_config::client::{
+    cache::now_secs,
+    connectivity::CONNECTIVITY,
+    http::{HttpError, HTTP_CLIENT},
+};
+use fit_launcher_scraping::source::repack_source;
+use http::{header, Request, Response, StatusCode};
+use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageFormat};
+use once_cell::sync::Lazy;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tempfile::NamedTempFile;
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::{error, warn};
+
+/// Total size of the stored pictures, unless the settings say otherwise.
+pub const IMAGE_STORE_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+/// A single picture larger than this is refused, covers and screenshots are far smaller.
+pub const MAX_IMAGE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Hosts the scraped pictures are downloaded from, their subdomains included.
+/// The repack site itself is accepted as well, some covers are uploaded there.
+pub const IMAGE_HOSTS: [&str; 2] = ["imageban.ru", "riotpixels.com"];
+
+/// Widths the thumbnails are generated at, pictures are never upscaled.
+pub const THUMBNAIL_WIDTHS: [u32; 3] = [320, 480, 640];
+
+/// Width of the placeholder, the webview stretches it over the whole picture.
+const PLACEHOLDER_WIDTH: u32 = 16;
+
+const INDEX_FILE_NAME: &str = "index.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredImage {
+    pub content_type: String,
+    pub size: u64,
+    /// Unix timestamps, in seconds.
+    pub stored_at: u64,
+    pub last_access: u64,
+    /// Generated on first use, pictures stored before thumbnails existed have none yet.
+    #[serde(default)]
+    pub preview: Option<StoredPreview>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredPreview {
+    pub width: u32,
+    pub height: u32,
+    /// Size of each thumbnail, keyed by its width.
+    pub thumbnails: BTreeMap<u32, u64>,
+    /// `data:` URL of the blurred placeholder.
+    pub placeholder: String,
+}
+
+/// What the frontend needs to lay a picture out before loading it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImagePreview {
+    pub hash: String,
+    pub width: u32,
+    pub height: u32,
+    /// Widths that can be asked for with `?w=`, wider requests get the original.
+    pub thumbnail_widths: Vec<u32>,
+    pub placeholder: String,
+}
+
+impl StoredImage {
+    /// Bytes taken on disk by the picture and its thumbnails.
+    fn disk_size(&self) -> u64 {
+        let thumbnails: u64 = self
+            .preview
+            .iter()
+            .flat_map(|preview| preview.thumbnails.values())
+            .sum();
+        self.size + thumbnails
+    }
+
+    fn image_preview(&self, hash: &str) -> Option<ImagePreview> {
+        let preview = self.preview.as_ref()?;
+        Some(ImagePreview {
+            hash: hash.to_string(),
+            width: preview.width,
+            height: preview.height,
+            thumbnail_widths: preview.thumbnails.keys().copied().collect(),
+            placeholder: preview.placeholder.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ImageIndex {
+    /// Keyed by the hash of the picture.
+    images: HashMap<String, StoredImage>,
+    /// Hash of the picture each source URL was downloaded as, several URLs can share one.
+    urls: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageStoreReport {
+    pub directory: PathBuf,
+    pub images: usize,
+    pub thumbnails: usize,
+    /// Source URLs pointing at the stored pictures.
+    pub urls: usize,
+    pub total_bytes: u64,
+    pub max_bytes: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImageStoreError {
+    #[error(transparent)]
+    Http(#[from] HttpError),
+
+    #[error("{0} is not an image")]
+    NotAnImage(String),
+
+    #[error("{0} is not hosted on a known image host")]
+    UnknownHost(String),
+
+    #[error("{0} is larger than {MAX_IMAGE_BYTES} bytes")]
+    TooLarge(String),
+
+    #[error("{0} is not in the image store")]
+    NotStored(String),
+
+    #[error("Failed to render the thumbnails: {0}")]
+    Rendering(#[from] image::ImageError),
+
+    #[error("Failed to access the image store: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl Serialize for ImageStoreError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
+
+pub struct ImageStore {
+    directory: PathBuf,
+    /// Loaded from disk on first use.
+    index: Mutex<Option<ImageIndex>>,
+    max_bytes: AtomicU64,
+}
+
+pub static IMAGE_STORE: Lazy<ImageStore> = Lazy::new(|| {
+    let directory = BaseDirs::new()
+        .expect("Failed to determine base directories")
+        .cache_dir()
+        .join("com.fitlauncher.carrotrub")
+        .join("images");
+
+    ImageStore::new(directory)
+});
+
+/// Hashes are the only file names the protocol accepts, nothing else of the disk can be read.
+fn is_hash(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Whether `url` points to one of the hosts the scraped pictures come from.
+fn is_known_image_host(url: &str) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+
+    let source_host = Url::parse(repack_source().base_url())
+        .ok()
+        .and_then(|base| base.host_str().map(str::to_string));
+    IMAGE_HOSTS
+        .iter()
+        .any(|known| host == *known || host.ends_with(&format!(".{}", known)))
+        || source_host.as_deref() == Some(host)
+}
+
+fn thumbnail_file_name(hash: &str, width: u32) -> String {
+    format!("{}.{}.webp", hash, width)
+}
+
+fn encode_webp(image: &DynamicImage) -> Result<Vec<u8>, image::ImageError> {
+    // The encoder only takes 8 bit pixels.
+    let image = if image.color().has_alpha() {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, ImageFormat::WebP)?;
+    Ok(bytes.into_inner())
+}
+
+struct RenderedPreview {
+    width: u32,
+    height: u32,
+    thumbnails: Vec<(u32, Vec<u8>)>,
+    placeholder: String,
+}
+
+/// Decode the picture once and produce every thumbnail and the placeholder from it.
+fn render_preview(bytes: &[u8]) -> Result<RenderedPreview, image::ImageError> {
+    let original = image::load_from_memory(bytes)?;
+    let (width, height) = original.dimensions();
+
+    let mut thumbnails = Vec::new();
+    for thumbnail_width in THUMBNAIL_WIDTHS {
+        if thumbnail_width >= width {
+            break;
+        }
+        let thumbnail = original.resize(thumbnail_width, u32::MAX, FilterType::Triangle);
+        thumbnails.push((thumbnail_width, encode_webp(&thumbnail)?));
+    }
+
+    let placeholder = original.thumbnail(PLACEHOLDER_WIDTH, u32::MAX).blur(1.0);
+    let placeholder = format!(
+        "data:image/webp;base64,{}",
+        STANDARD.encode(encode_webp(&placeholder)?)
+    );
+
+    Ok(RenderedPreview {
+        width,
+        height,
+        thumbnails,
+        placeholder,
+    })
+}
+
+/// Write through a temp file of its own, two writes of a same file can't swap their halves.
+async fn write_atomically(
+    directory: &Path,
+    file_name: &str,
+    bytes: Vec<u8>,
+) -> std::io::Result<()> {
+    let directory = directory.to_path_buf();
+    let path = directory.join(file_name);
+
+    tokio::task::spawn_blocking(move || {
+        let mut temp_file = NamedTempFile::new_in(&directory)?;
+        temp_file.write_all(&bytes)?;
+        temp_file.persist(path).map_err(|e| e.error)?;
+        Ok(())
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+impl ImageStore {
+    pub fn new(directory: PathBuf) -> Self {
+        ImageStore {
+            directory,
+            index: Mutex::new(None),
+            max_bytes: AtomicU64::new(IMAGE_STORE_MAX_BYTES),
+        }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Takes effect with the next stored picture.
+    pub fn set_max_bytes(&self, max_bytes: u64) {
+        self.max_bytes.store(max_bytes, Ordering::Relaxed);
+    }
+
+    async fn index(&self) -> MutexGuard<'_, Option<ImageIndex>> {
+        let mut guard = self.index.lock().await;
+
+        if guard.is_none() {
+            let index_path = self.directory.join(INDEX_FILE_NAME);
+            let index = match tokio::fs::read_to_string(&index_path).await {
+                Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                    warn!("Image store index is corrupted, starting over: {}", e);
+                    ImageIndex::default()
+                }),
+                Err(_) => ImageIndex::default(),
+            };
+            *guard = Some(index);
+        }
+
+        guard
+    }
+
+    async fn save_index(&self, index: &ImageIndex) -> std::io::Result<()> {
+        tokio::fs::create_dir_all(&self.directory).await?;
+
+        write_atomically(&self.directory, INDEX_FILE_NAME, serde_json::to_vec(index)?).await
+    }
+
+    /// Hash of the picture `url` was stored as, if it is still on disk.
+    pub async fn hash_of(&self, url: &str) -> Option<String> {
+        let guard = self.index().await;
+        let hash = guard.as_ref()?.urls.get(url)?.clone();
+
+        tokio::fs::try_exists(self.directory.join(&hash))
+            .await
+            .unwrap_or(false)
+            .then_some(hash)
+    }
+
+    /// Download `url` unless it is already stored, returns the hash it is served under.
+    pub async fn store_url(&self, url: &str) -> Result<String, ImageStoreError> {
+        if let Some(hash) = self.hash_of(url).await {
+            return Ok(hash);
+        }
+        if !is_known_image_host(url) {
+            return Err(ImageStoreError::UnknownHost(url.to_string()));
+        }
+        if CONNECTIVITY.is_offline() {
+            return Err(HttpError::Offline {
+                url: url.to_string(),
+            }
+            .into());
+        }
+
+        let mut response = HTTP_CLIENT.send(HTTP_CLIENT.get(url)).await?;
+        if !response.status().is_success() {
+            return Err(HttpError::Status {
+                url: url.to_string(),
+                status: response.status(),
+            }
+            .into());
+        }
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_IMAGE_BYTES)
+        {
+            return Err(ImageStoreError::TooLarge(url.to_string()));
+        }
+
+        // The length may be missing or wrong, the cap is checked again while reading.
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|source| HttpError::Request {
+                url: url.to_string(),
+                source,
+            })?
+        {
+            if (bytes.len() + chunk.len()) as u64 > MAX_IMAGE_BYTES {
+                return Err(ImageStoreError::TooLarge(url.to_string()));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        // Image hosts answer missing pictures with an HTML page and a 200.
+        let format = image::guess_format(&bytes)
+            .map_err(|_| ImageStoreError::NotAnImage(url.to_string()))?;
+        let hash = format!("{:x}", Sha256::digest(&bytes));
+        let size = bytes.len() as u64;
+
+        let mut guard = self.index().await;
+        let index = guard.get_or_insert_with(ImageIndex::default);
+
+        let image_path = self.directory.join(&hash);
+        if !tokio::fs::try_exists(&image_path).await.unwrap_or(false) {
+            tokio::fs::create_dir_all(&self.directory).await?;
+            write_atomically(&self.directory, &hash, bytes).await?;
+        }
+
+        let now = now_secs();
+        // Another URL may have brought the same picture, its thumbnails still apply.
+        let preview = index
+            .images
+            .get(&hash)
+            .and_then(|image| image.preview.clone());
+        index.images.insert(
+            hash.clone(),
+            StoredImage {
+                content_type: format.to_mime_type().to_string(),
+                size,
+                stored_at: now,
+                last_access: now,
+                preview,
+            },
+        );
+        index.urls.insert(url.to_string(), hash.clone());
+
+        self.evict_over_limit(index).await;
+        self.save_index(index).await?;
+        Ok(hash)
+    }
+
+    /// The bytes and content type of the picture stored as `hash`.
+    ///
+    /// The access time is only kept in memory, it is saved with the next stored picture.
+    pub async fn read(&self, hash: &str) -> Option<(Vec<u8>, String)> {
+        if !is_hash(hash) {
+            return None;
+        }
+
+        let mut guard = self.index().await;
+        let image = guard.as_mut()?.images.get_mut(hash)?;
+        image.last_access = now_secs();
+        let content_type = image.content_type.clone();
+        drop(guard);
+
+        match tokio::fs::read(self.directory.join(hash)).await {
+            Ok(bytes) => Some((bytes, content_type)),
+            Err(e) => {
+                warn!("Stored picture {} is unreadable: {}", hash, e);
+                None
+            }
+        }
+    }
+
+    /// The smallest thumbnail of `hash` at least `min_width` wide, the original if there is none.
+    pub async fn read_thumbnail(&self, hash: &str, min_width: u32) -> Option<(Vec<u8>, String)> {
+        let thumbnail_width = match self.prepare(hash).await {
+            Ok(preview) => preview
+                .thumbnail_widths
+                .into_iter()
+                .find(|width| *width >= min_width),
+            Err(e) => {
+                warn!("No thumbnails for {}: {}", hash, e);
+                None
+            }
+        };
+        let Some(thumbnail_width) = thumbnail_width else {
+            return self.read(hash).await;
+        };
+
+        let mut guard = self.index().await;
+        if let Some(image) = guard.as_mut().and_then(|index| index.images.get_mut(hash)) {
+            image.last_access = now_secs();
+        }
+        drop(guard);
+
+        let thumbnail_path = self
+            .directory
+            .join(thumbnail_file_name(hash, thumbnail_width));
+        match tokio::fs::read(thumbnail_path).await {
+            Ok(bytes) => Some((bytes, "image/webp".to_string())),
+            Err(e) => {
+                warn!(
+                    "Thumbnail {} of {} is unreadable: {}",
+                    thumbnail_width, hash, e
+                );
+                self.read(hash).await
+            }
+        }
+    }
+
+    /// Generate the thumbnails and placeholder of `hash` unless it already has them.
+    pub async fn prepare(&self, hash: &str) -> Result<ImagePreview, ImageStoreError> {
+        let not_stored = || ImageStoreError::NotStored(hash.to_string());
+        if !is_hash(hash) {
+            return Err(not_stored());
+        }
+
+        let guard = self.index().await;
+        let image = guard
+            .as_ref()
+            .and_then(|index| index.images.get(hash))
+            .ok_or_else(not_stored)?;
+        if let Some(preview) = image.image_preview(hash) {
+            return Ok(preview);
+        }
+        drop(guard);
+
+        // Decoding and resizing take a while, the index stays available meanwhile.
+        let bytes = tokio::fs::read(self.directory.join(hash)).await?;
+        let rendered = tokio::task::spawn_blocking(move || render_preview(&bytes))
+            .await
+            .map_err(std::io::Error::other)??;
+
+        let mut thumbnails = BTreeMap::new();
+        for (width, bytes) in rendered.thumbnails {
+            let size = bytes.len() as u64;
+            write_atomically(&self.directory, &thumbnail_file_name(hash, width), bytes).await?;
+            thumbnails.insert(width, size);
+        }
+
+        let mut guard = self.index().await;
+        let index = guard.get_or_insert_with(ImageIndex::default);
+        let Some(image) = index.images.get_mut(hash) else {
+            // Evicted while rendering.
+            for width in thumbnails.keys() {
+                let _ =
+                    tokio::fs::remove_file(self.directory.join(thumbnail_file_name(hash, *width)))
+                        .await;
+            }
+            return Err(not_stored());
+        };
+        image.preview = Some(StoredPreview {
+            width: rendered.width,
+            height: rendered.height,
+            thumbnails,
+            placeholder: rendered.placeholder,
+        });
+        let preview = image.image_preview(hash).ok_or_else(not_stored)?;
+
+        self.evict_over_limit(index).await;
+        self.save_index(index).await?;
+        Ok(preview)
+    }
+
+    /// Delete the picture stored as `hash` and its thumbnails.
+    async fn remove_files(&self, hash: &str, image: &StoredImage) {
+        if let Err(e) = tokio::fs::remove_file(self.directory.join(hash)).await {
+            warn!("Failed to remove stored picture {}: {}", hash, e);
+        }
+        for width in image
+            .preview
+            .iter()
+            .flat_map(|preview| preview.thumbnails.keys())
+        {
+            let thumbnail_path = self.directory.join(thumbnail_file_name(hash, *width));
+            if let Err(e) = tokio::fs::remove_file(thumbnail_path).await {
+                warn!("Failed to remove thumbnail {} of {}: {}", width, hash, e);
+            }
+        }
+    }
+
+    /// Drop the least recently used pictures until the store fits its limit.
+    async fn evict_over_limit(&self, index: &mut ImageIndex) {
+        let max_bytes = self.max_bytes();
+        let mut total_bytes: u64 = index.images.values().map(StoredImage::disk_size).sum();
+        if total_bytes <= max_bytes {
+            return;
+        }
+
+        let mut by_access: Vec<(u64, String)> = index
+            .images
+            .iter()
+            .map(|(hash, image)| (image.last_access, hash.clone()))
+            .collect();
+        by_access.sort();
+
+        for (_, hash) in by_access {
+            if total_bytes <= max_bytes {
+                break;
+            }
+            if let Some(image) = index.images.remove(&hash) {
+                total_bytes -= image.disk_size();
+                self.remove_files(&hash, &image).await;
+            }
+        }
+
+        index.urls.retain(|_, hash| index.images.contains_key(hash));
+    }
+
+    /// Forget `urls`, the pictures no other URL points at are deleted.
+    pub async fn forget_urls(&self, urls: &[String]) -> std::io::Result<()> {
+        let mut guard = self.index().await;
+        let Some(index) = guard.as_mut() else {
+            return Ok(());
+        };
+
+        let forgotten: Vec<String> = urls
+            .iter()
+            .filter_map(|url| index.urls.remove(url))
+            .collect();
+        for hash in forgotten {
+            if index.urls.values().any(|other| *other == hash) {
+                continue;
+            }
+            if let Some(image) = index.images.remove(&hash) {
+                self.remove_files(&hash, &image).await;
+            }
+        }
+
+        self.save_index(index).await
+    }
+
+    pub async fn report(&self) -> ImageStoreReport {
+        let guard = self.index().await;
+        let images: Vec<&StoredImage> = guard
+            .as_ref()
+            .map(|index| index.images.values().collect())
+            .unwrap_or_default();
+
+        ImageStoreReport {
+            directory: self.directory.clone(),
+            images: images.len(),
+            thumbnails: images
+                .iter()
+                .flat_map(|image| image.preview.iter())
+                .map(|preview| preview.thumbnails.len())
+                .sum(),
+            urls: guard.as_ref().map_or(0, |index| index.urls.len()),
+            total_bytes: images.iter().map(|image| image.disk_size()).sum(),
+            max_bytes: self.max_bytes(),
+        }
+    }
+
+    /// Delete every stored picture.
+    pub async fn clear(&self) -> std::io::Result<()> {
+        let mut guard = self.index.lock().await;
+        *guard = Some(ImageIndex::default());
+
+        match tokio::fs::remove_dir_all(&self.directory).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn empty_response(status: StatusCode) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .body(Vec::new())
+        .expect("A response without headers is always valid")
+}
+
+/// Answer a `fitimg` request, by hash or by source URL, optionally as a thumbnail.
+pub async fn serve_image(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let query: HashMap<String, String> = Url::parse(&request.uri().to_string())
+        .map(|uri| uri.query_pairs().into_owned().collect())
+        .unwrap_or_default();
+    let source_url = query.get("url").cloned();
+    let min_width = query.get("w").and_then(|width| width.parse::<u32>().ok());
+
+    let hash = match source_url {
+        Some(url) => match IMAGE_STORE.store_url(&url).await {
+            Ok(hash) => hash,
+            Err(e @ ImageStoreError::UnknownHost(_)) => {
+                warn!("Refused to serve {}: {}", url, e);
+                return empty_response(StatusCode::FORBIDDEN);
+            }
+            Err(e) => {
+                error!("Failed to store {}: {}", url, e);
+                return empty_response(StatusCode::BAD_GATEWAY);
+            }
+        },
+        None => request.uri().path().trim_matches('/').to_string(),
+    };
+
+    let served = match min_width {
+        Some(min_width) => IMAGE_STORE.read_thumbnail(&hash, min_width).await,
+        None => IMAGE_STORE.read(&hash).await,
+    };
+    let Some((bytes, content_type)) = served else {
+        return empty_response(StatusCode::NOT_FOUND);
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        // The same hash always holds the same bytes.
+        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(bytes)
+        .unwrap_or_else(|_| empty_response(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+/// Store the picture at `url`, returns the hash to load it with from `fitimg://localhost/`.
+#[tauri::command]
+pub async fn cache_image(url: String) -> Result<String, ImageStoreError> {
+    IMAGE_STORE.store_url(&url).await
+}
+
+/// Store the picture at `url` and generate its thumbnails and placeholder.
+#[tauri::command]
+pub async fn get_image_preview(url: String) -> Result<ImagePreview, ImageStoreError> {
+    let hash = IMAGE_STORE.store_url(&url).await?;
+    IMAGE_STORE.prepare(&hash).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_hashes_name_stored_files() {
+        assert!(is_hash(&"0123456789abcdef".repeat(4)));
+        assert!(is_hash(&"0123456789ABCDEF".repeat(4)));
+        assert!(!is_hash(&"0".repeat(63)));
+        assert!(!is_hash(&"0".repeat(65)));
+        assert!(!is_hash(&format!("../{}", "0".repeat(61))));
+        assert!(!is_hash(&"g".repeat(64)));
+    }
+
+    #[test]
+    fn downloads_only_from_the_known_image_hosts() {
+        assert!(is_known_image_host("https://imageban.ru/out/cover.jpg"));
+        assert!(is_known_image_host("https://i5.imageban.ru/out/cover.jpg"));
+        assert!(is_known_image_host(
+            "http://images.riotpixels.com/cover.jpg"
+        ));
+
+        assert!(!is_known_image_host(
+            "https://evil-imageban.ru/out/cover.jpg"
+        ));
+        assert!(!is_known_image_host(
+            "https://imageban.ru.evil.com/out/cover.jpg"
+        ));
+        assert!(!is_known_image_host("ftp://imageban.ru/out/cover.jpg"));
+        assert!(!is_known_image_host("file:///etc/passwd"));
+        assert!(!is_known_image_host("imageban.ru/out/cover.jpg"));
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            width,
+            height,
+            image::Rgb([200, 40, 90]),
+        ));
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn renders_thumbnails_narrower_than_the_picture() {
+        let rendered = render_preview(&png(1000, 500)).unwrap();
+        assert_eq!((rendered.width, rendered.height), (1000, 500));
+        assert!(rendered.placeholder.starts_with("data:image/webp;base64,"));
+
+        let widths: Vec<u32> = rendered
+            .thumbnails
+            .iter()
+            .map(|(width, _)| *width)
+            .collect();
+        assert_eq!(widths, THUMBNAIL_WIDTHS);
+        for (width, bytes) in &rendered.thumbnails {
+            let thumbnail = image::load_from_memory_with_format(bytes, ImageFormat::WebP).unwrap();
+            assert_eq!(thumbnail.dimensions(), (*width, *width / 2));
+        }
+
+        // Pictures are never upscaled.
+        let rendered = render_preview(&png(400, 300)).unwrap();
+        let widths: Vec<u32> = rendered
+            .thumbnails
+            .iter()
+            .map(|(width, _)| *width)
+            .collect();
+        assert_eq!(widths, [320]);
+    }
+
+    fn stored_image(size: u64, last_access: u64, thumbnails: &[(u32, u64)]) -> StoredImage {
+        StoredImage {
+            content_type: "image/png".to_string(),
+            size,
+            stored_at: 0,
+            last_access,
+            preview: Some(StoredPreview {
+                width: 1000,
+                height: 500,
+                thumbnails: thumbnails.iter().copied().collect(),
+                placeholder: String::new(),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn evicts_the_least_recently_used_pictures_with_their_thumbnails() {
+        let directory = tempfile::tempdir().unwrap();
+        let store = ImageStore::new(directory.path().to_path_buf());
+        let (recent, oldest, older) = ("a".repeat(64), "b".repeat(64), "c".repeat(64));
+
+        let mut index = ImageIndex::default();
+        for (hash, last_access, thumbnails) in [
+            (&recent, 30, vec![]),
+            (&oldest, 10, vec![(320, 50)]),
+            (&older, 20, vec![]),
+        ] {
+            std::fs::write(directory.path().join(hash), [0; 100]).unwrap();
+            for (width, size) in &thumbnails {
+                std::fs::write(
+                    directory.path().join(thumbnail_file_name(hash, *width)),
+                    vec![0; *size as usize],
+                )
+                .unwrap();
+            }
+            index
+                .images
+                .insert(hash.clone(), stored_image(100, last_access, &thumbnails));
+            index.urls.insert(
+                format!("https://imageban.ru/{}.png", last_access),
+                hash.clone(),
+            );
+        }
+
+        // Nothing goes while the store fits.
+        store.set_max_bytes(350);
+        store.evict_over_limit(&mut index).await;
+        assert_eq!(index.images.len(), 3);
+
+        // The thumbnails count, dropping the oldest picture is enough.
+        store.set_max_bytes(250);
+        store.evict_over_limit(&mut index).await;
+        assert!(index.images.contains_key(&recent));
+        assert!(index.images.contains_key(&older));
+        assert!(!index.images.contains_key(&oldest));
+        assert!(!index.urls.values().any(|hash| *hash == oldest));
+        assert_eq!(index.urls.len(), 2);
+        assert!(!directory.path().join(&oldest).exists());
+        assert!(!directory
+            .path()
+            .join(thumbnail_file_name(&oldest, 320))
+            .exists());
+        assert!(directory.path().join(&older).exists());
+    }
+}
@@ -1,20 +1,20 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 use std::fs;
+mod image_cache;
 mod image_colors;
+mod image_store;
 mod game_info;
+mod connectivity;
+mod scheduler;
 use fit_launcher_config::settings::creation::create_gamehub_settings_file;
-use fit_launcher_config::settings::creation::create_image_cache_file;
 use fit_launcher_config::settings::creation::create_installation_settings_file;
 use fit_launcher_config::settings::creation::create_realdebrid_settings_file;
-use fit_launcher_scraping::discovery::get_100_games_unordered;
-use fit_launcher_scraping::get_sitemaps_website;
-use fit_launcher_scraping::global::functions::popular_games_scraping_func;
-use fit_launcher_scraping::global::functions::recently_updated_games_scraping_func;
-use fit_launcher_scraping::global::functions::scraping_func;
-use serde_json::Value;
-use std::error::Error;
-use tauri::async_runtime::spawn_blocking;
+use fit_launcher_scraping::search::SearchIndexState;
+use fit_launcher_scraping::store::GAME_STORE;
+use connectivity::{check_connectivity, emit_network_failure, refresh_all_feeds, start_connectivity_watch};
+use fit_launcher_config::client::connectivity::{Connectivity, CONNECTIVITY};
+use scheduler::start_scheduler;
 use tauri::menu::Menu;
 use tauri::menu::MenuItem;
 use tauri::Emitter;
@@ -24,114 +24,42 @@ use tauri::Manager;
 use std::time::Instant;
 use tauri::async_runtime::spawn;
 use tauri::tray::TrayIconBuilder;
-use std::path::Path;
-use anyhow::Result;
-use std::sync::
-    Arc
-;
-use lru::LruCache;
-use std::num::NonZeroUsize;
-use tokio::sync::Mutex;
 use chrono::Utc;
 pub mod utils;
 pub use utils::*;
+pub use image_cache::*;
 pub use image_colors::*;
+pub use image_store::*;
 pub use game_info::*;
 use fit_launcher_real_debrid::auth::AuthState;
 use fit_launcher_real_debrid::client::Client;
 
-fn delete_invalid_json_files(app_handle: &tauri::AppHandle) -> Result<(), Box<dyn Error>> {
-    let mut dir_path = app_handle.path().app_data_dir().unwrap();
-    dir_path.push("tempGames");
-
-    if !dir_path.exists() {
-        info!("Directory does not exist: {:?}", dir_path);
-        return Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "Directory not found",
-        )));
-    }
-
-    // Read the folder and iterate over the files
-    for entry in fs::read_dir(dir_path)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        // Only process JSON files
-        if path.extension().and_then(|s| s.to_str()) == Some("json") {
-            if let Err(e) = check_file_for_tags(&path) {
-                error!("Error processing file {:?}: {}", path, e);
-            }
-        }
-    }
-
-    Ok(())
-}
-
-// Function to check a single JSON file for the "tag" key in each object
-fn check_file_for_tags(path: &Path) -> Result<()> {
-    // Read the file content as a string
-    let file_content = fs::read_to_string(path)?;
-
-    // Parse the JSON content into a Value object
-    let json: Value = serde_json::from_str(&file_content)?;
-
-    // Ensure the JSON is an array of objects
-    if let Some(arr) = json.as_array() {
-        for obj in arr {
-            // Each object should contain the "tag" key
-            if obj.get("tag").is_none() {
-                warn!("Missing 'tag' key in file: {:?}, rebuilding...", path);
-                fs::remove_file(path)?;
-            }
-        }
-    }
-
-    Ok(())
-}
-
-// Function to perform a network request after ensuring frontend is ready, and emit network-failure if the request fails
-async fn perform_network_request(app_handle: tauri::AppHandle) {
-    info!(
-        "perform_network_request: Waiting for frontend-ready before starting the network request."
-    );
+// Tell the frontend the site can't be reached once it listens, the first probe may be done before it is
+async fn report_connectivity_when_ready(app_handle: tauri::AppHandle) {
+    info!("report_connectivity_when_ready: Waiting for frontend-ready before reporting connectivity.");
 
     // Get the main window to listen for 'frontend-ready'
     if let Some(main_window) = app_handle.get_webview_window("main") {
         // Clone `app_handle` so it can be moved into both closures
         let app_handle_clone = app_handle.clone();
 
-        // Listen for 'frontend-ready' before performing the network request
         main_window.listen("frontend-ready", move |_| {
-            info!("Frontend is ready, starting the network request...");
+            info!("Frontend is ready, reporting connectivity...");
 
-            // Clone `app_handle_clone` for use in the async block
             let app_handle_inner_clone = app_handle_clone.clone();
 
-            // Perform the network request directly to the site - This is lazy but it works for now!
-            //TODO: improve this
             spawn(async move {
-                let result = reqwest::get("https://fitgirl-repacks.site").await;
-
-                // Check if the request was successful
-                match result {
-                    Ok(resp) => {
-                        let _text = resp.text().await.unwrap();
-                        info!(
-                            "perform_network_request: Network request to Fitgirl website was successful."
-                        );
+                match CONNECTIVITY.state() {
+                    // The probe emits network-failure itself if it fails
+                    Connectivity::Unknown => {
+                        check_connectivity(&app_handle_inner_clone).await;
                     }
-                    Err(_) => {
-                        info!("Network request failed, emitting network-failure event.");
-
-                        // Emit the network-failure event after the network request fails
-                        let failure_message = Payload {
-                            message: "There was a network issue, unable to retrieve latest game data. (E01)".to_string(),
-                        };
-                        app_handle_inner_clone
-                            .emit("network-failure", failure_message)
-                            .unwrap();
+                    Connectivity::Offline { .. } => {
+                        emit_network_failure(&app_handle_inner_clone);
                     }
+                    Connectivity::Online { .. } => info!(
+                        "report_connectivity_when_ready: Fitgirl website can be reached."
+                    ),
                 }
             });
         });
@@ -164,10 +92,6 @@ async fn start() {
         "{}: Main.rs: Starting the application...",
         Utc::now().format("%a,%b,%e,%T,%Y")
     );
-    let image_cache = Arc::new(Mutex::new(LruCache::<String, Vec<String>>::new(
-        NonZeroUsize::new(30).unwrap(),
-    )));
-
     tauri::Builder
         ::default()
         .plugin(tauri_plugin_store::Builder::new().build())
@@ -219,6 +143,10 @@ async fn start() {
 
             let app_handle = app.handle().clone();
 
+            // The game page reads the screenshot links from the app cache directory too
+            app.manage(ImageCacheService::new(app.path().app_cache_dir()?));
+            follow_image_cache_settings(app_handle.clone());
+
             let _scraping_failed_event = app_handle.clone();
             let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
             let show_app_i = MenuItem::with_id(app, "show_app", "Show App", true, None::<&str>)?;
@@ -267,13 +195,8 @@ async fn start() {
               })
               .build(app)?;
 
-            // Delete JSON files missing the 'tag' field or corrupted and log the process
-            if let Err(err) = delete_invalid_json_files(&current_app_handle) {
-                eprintln!(
-                    "Error during deletion of invalid or corrupted JSON files: {}",
-                    err
-                );
-            }
+            // Rebuild the feed files from the game store, a crash can't leave them half written
+            GAME_STORE.export_all();
 
             // Create the settings file if they haven't been created already
             if let Err(err) = create_installation_settings_file() {
@@ -286,101 +209,24 @@ async fn start() {
                 eprintln!("Error while creating the gamehub settings file : {}", err)
             }
 
-            if let Err(err) = create_image_cache_file() {
-                error!("Error while creating the image cache file : {}", err);
-                eprintln!("Error while creating the image cache file : {}", err)
-            }
-            
             if let Err(err) = create_realdebrid_settings_file() {
                 error!("Error while creating the realdebrid settings file : {}", err);
                 eprintln!("Error while creating the realdebrid settings file : {}", err)
             }
 
 
-            // Perform the network request
             spawn(async move {
-                perform_network_request(app_handle).await;
+                report_connectivity_when_ready(app_handle).await;
             });
             
 
-            // Clone the app handle for use in async tasks
-            let first_app_handle = current_app_handle.clone();
-            let second_app_handle = current_app_handle.clone();
-            let third_app_handle = current_app_handle.clone();
-            let fourth_app_handle = current_app_handle.clone();
-
             // Perform asynchronous initialization tasks without blocking the main thread
             tauri::async_runtime::spawn(async move {
                 tracing::info!("Starting async tasks");
 
-                // Spawn blocking tasks for each `#[tokio::main]` function
-                let task_1 = spawn_blocking(|| {
-                    if let Err(e) = get_100_games_unordered() {
-                        eprintln!("Error in get_100_games_unordered: {}", e);
-                        tracing::info!("Error in get_100_games_unordered: {}", e);
-                    } else {
-                        tracing::info!(
-                            "[get_100_games_unordered] has been completed. No errors are reported."
-                        );
-                    }
-                });
-            
-                let task_2 = spawn_blocking(move || {
-                    let first_app_handle_clone = first_app_handle.clone();
-                    if let Err(e) = scraping_func(first_app_handle_clone.clone()) {
-                        eprintln!("Error in scraping_func: {}", e);
-                        tracing::info!("Error in scraping_func: {}", e);
-                    } else {
-                        tracing::info!("[scraping_func] has been completed. No errors are reported.");
-                        first_app_handle_clone.emit("new-games-ready", {}).unwrap();
-                    }
-                });
-            
-                let task_3 = spawn_blocking(move || {
-                    let second_app_handle_clone = second_app_handle.clone();
-                    if let Err(e) = popular_games_scraping_func(second_app_handle_clone.clone()) {
-                        eprintln!("Error in popular_games_scraping_func: {}", e);
-                        tracing::info!("Error in popular_games_scraping_func: {}", e);
-                    } else {
-                        tracing::info!(
-                            "[popular_games_scraping_func] has been completed. No errors are reported."
-                        );
-                        second_app_handle_clone.emit("popular-games-ready", {}).unwrap();
-                    }
-                });
-            
-                let task_4 = spawn_blocking(move || {
-                    let third_app_handle_clone = third_app_handle.clone();
-                    if let Err(e) =
-                        recently_updated_games_scraping_func(third_app_handle_clone.clone())
-                    {
-                        eprintln!("Error in recently_updated_games_scraping_func: {}", e);
-                        tracing::info!("Error in recently_updated_games_scraping_func: {}", e);
-                    } else {
-                        tracing::info!(
-                            "[recently_updated_games_scraping_func] has been completed. No errors are reported."
-                        );
-                        third_app_handle_clone
-                            .emit("recent-updated-games-ready", {})
-                            .unwrap();
-                    }
-                });
-            
-                let task_5 = spawn_blocking(move || {
-                    let fourth_app_handle_clone = fourth_app_handle.clone();
-                    if let Err(e) = get_sitemaps_website(fourth_app_handle_clone.clone()) {
-                        eprintln!("Error in get_sitemaps_website: {}", e);
-                        tracing::info!("Error in get_sitemaps_website: {}", e);
-                    } else {
-                        tracing::info!(
-                            "[get_sitemaps_website] has been completed. No errors are reported."
-                        );
-                        fourth_app_handle_clone.emit("sitemaps-ready", {}).unwrap();
-                    }
-                });
-            
-                // Wait for all tasks to complete
-                let _ = tokio::join!(task_1, task_2, task_3, task_4, task_5);
+                // Feeds are only scraped when the site answers, the stored ones are shown otherwise
+                check_connectivity(&current_app_handle).await;
+                refresh_all_feeds(current_app_handle.clone()).await;
             
                 // After all tasks are done, close the splash screen and show the main window
                 splashscreen_window.close().unwrap();
@@ -400,6 +246,10 @@ async fn start() {
                     "It took : {:#?} seconds for the app to fully get every information!",
                     start_time.elapsed()
                 );
+
+                // From now on every feed is refreshed on its own interval
+                start_scheduler(current_app_handle.clone());
+                start_connectivity_watch(current_app_handle);
             });
             
             
@@ -410,10 +260,16 @@ async fn start() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .register_asynchronous_uri_scheme_protocol("fitimg", |_ctx, request, responder| {
+            spawn(async move {
+                responder.respond(serve_image(request).await);
+            });
+        })
         .invoke_handler(tauri_helper::tauri_collect_commands!())
-        .manage(image_cache) // Make the cache available to commands
         .manage(AuthState::new())
         .manage(Client::new())
+        .manage(SearchIndexState::default())
+        .manage(ImageFetchJobs::default())
                 .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app_handle, event| {
@@ -0,0 +1,218 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use fit_launcher_config::client::connectivity::CONNECTIVITY;
+use fit_launcher_config::settings::config::get_gamehub_settings;
+use fit_launcher_config::settings::creation::FeedRefreshSettings;
+use fit_launcher_scraping::discovery::get_100_games_unordered;
+use fit_launcher_scraping::get_sitemaps_website;
+use fit_launcher_scraping::global::functions::popular_games_scraping_func;
+use fit_launcher_scraping::global::functions::recently_updated_games_scraping_func;
+use fit_launcher_scraping::global::functions::scraping_func;
+use fit_launcher_scraping::health::announce_degraded_reports;
+use fit_launcher_scraping::notify_outdated_games;
+use rand::Rng;
+use tauri::async_runtime::spawn_blocking;
+use tauri::Emitter;
+use tokio::sync::Notify;
+use tracing::{error, info};
+
+/// How long a disabled feed waits before looking at its settings again.
+const DISABLED_FEED_RECHECK: Duration = Duration::from_secs(5 * 60);
+
+/// Set while a feed is being scraped, indexed by [`Feed`].
+static REFRESHING: [AtomicBool; Feed::ALL.len()] =
+    [const { AtomicBool::new(false) }; Feed::ALL.len()];
+/// Cuts short the wait of a feed loop for its next refresh, indexed by [`Feed`].
+static WAKE_UP: [Notify; Feed::ALL.len()] = [const { Notify::const_new() }; Feed::ALL.len()];
+
+#[derive(Debug, Clone, Copy)]
+pub enum Feed {
+    NewlyAdded,
+    Popular,
+    RecentlyUpdated,
+    Discovery,
+    Sitemaps,
+}
+
+impl Feed {
+    pub const ALL: [Feed; 5] = [
+        Feed::Discovery,
+        Feed::NewlyAdded,
+        Feed::Popular,
+        Feed::RecentlyUpdated,
+        Feed::Sitemaps,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Feed::NewlyAdded => "scraping_func",
+            Feed::Popular => "popular_games_scraping_func",
+            Feed::RecentlyUpdated => "recently_updated_games_scraping_func",
+            Feed::Discovery => "get_100_games_unordered",
+            Feed::Sitemaps => "get_sitemaps_website",
+        }
+    }
+
+    /// The event the frontend listens to, the discovery feed has none.
+    fn ready_event(self) -> Option<&'static str> {
+        match self {
+            Feed::NewlyAdded => Some("new-games-ready"),
+            Feed::Popular => Some("popular-games-ready"),
+            Feed::RecentlyUpdated => Some("recent-updated-games-ready"),
+            Feed::Discovery => None,
+            Feed::Sitemaps => Some("sitemaps-ready"),
+        }
+    }
+
+    /// Mark the feed as being scraped, `None` if it already is.
+    fn start_refresh(self) -> Option<RefreshGuard> {
+        REFRESHING[self as usize]
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| RefreshGuard(self))
+    }
+
+    fn interval_minutes(self, settings: &FeedRefreshSettings) -> u64 {
+        match self {
+            Feed::NewlyAdded => settings.newly_added_minutes,
+            Feed::Popular => settings.popular_minutes,
+            Feed::RecentlyUpdated => settings.recently_updated_minutes,
+            Feed::Discovery => settings.discovery_minutes,
+            Feed::Sitemaps => settings.sitemaps_minutes,
+        }
+    }
+}
+
+/// Clears the refreshing flag of its feed when dropped, even if the scrape panicked.
+struct RefreshGuard(Feed);
+
+impl Drop for RefreshGuard {
+    fn drop(&mut self) {
+        REFRESHING[self.0 as usize].store(false, Ordering::Release);
+    }
+}
+
+/// Scrape one feed and emit its ready event, blocks until the scrape is done.
+///
+/// The scrape functions are `#[tokio::main]`, so this must run on a blocking thread.
+/// A feed already being scraped is skipped, both runs would write the same files.
+/// Returns whether the feed was scraped without errors.
+pub fn refresh_feed(feed: Feed, app_handle: tauri::AppHandle) -> bool {
+    // The stored feeds are kept as they are, they are refreshed once the site answers again.
+    if CONNECTIVITY.is_offline() {
+        info!("[{}] skipped, the site can't be reached.", feed.name());
+        return false;
+    }
+
+    let Some(_refreshing) = feed.start_refresh() else {
+        info!("[{}] skipped, a refresh is already running.", feed.name());
+        return false;
+    };
+
+    let result = match feed {
+        Feed::NewlyAdded => scraping_func().map_err(|e| e.to_string()),
+        Feed::Popular => popular_games_scraping_func(app_handle.clone()).map_err(|e| e.to_string()),
+        Feed::RecentlyUpdated => {
+            recently_updated_games_scraping_func(app_handle.clone()).map_err(|e| e.to_string())
+        }
+        Feed::Discovery => get_100_games_unordered().map_err(|e| e.to_string()),
+        Feed::Sitemaps => get_sitemaps_website(app_handle.clone()).map_err(|e| e.to_string()),
+    };
+
+    // A scrape can find its pages and still parse nothing out of them.
+    announce_degraded_reports(&app_handle);
+
+    match result {
+        Err(e) => {
+            eprintln!("Error in {}: {}", feed.name(), e);
+            error!("Error in {}: {}", feed.name(), e);
+            false
+        }
+        Ok(()) => {
+            info!(
+                "[{}] has been completed. No errors are reported.",
+                feed.name()
+            );
+            if let Some(event) = feed.ready_event() {
+                if let Err(e) = app_handle.emit(event, {}) {
+                    error!("Failed to emit {}: {}", event, e);
+                }
+            }
+            true
+        }
+    }
+}
+
+/// Run [`refresh_feed`] on a blocking thread and wait for it.
+///
+/// A fresh digest is what tells which installed repacks got updated, so the
+/// library is checked once the recently updated feed has been scraped.
+pub async fn run_feed_refresh(feed: Feed, app_handle: tauri::AppHandle) {
+    let refresh_handle = app_handle.clone();
+    match spawn_blocking(move || refresh_feed(feed, refresh_handle)).await {
+        Ok(true) if matches!(feed, Feed::RecentlyUpdated) => {
+            notify_outdated_games(&app_handle).await;
+        }
+        Ok(_) => {}
+        Err(e) => error!("Refresh task of {} panicked: {}", feed.name(), e),
+    }
+}
+
+/// Interval of `feed` with the configured jitter applied, `None` when disabled.
+fn next_delay(feed: Feed) -> Option<Duration> {
+    let settings = get_gamehub_settings().unwrap_or_default().feed_refresh;
+    let minutes = feed.interval_minutes(&settings);
+    if minutes == 0 {
+        return None;
+    }
+
+    let base_secs = minutes * 60;
+    let spread = base_secs * u64::from(settings.jitter_percent.min(100)) / 100;
+    let secs = if spread == 0 {
+        base_secs
+    } else {
+        rand::rng().random_range(base_secs.saturating_sub(spread)..=base_secs + spread)
+    };
+
+    Some(Duration::from_secs(secs.max(60)))
+}
+
+/// Keep every feed fresh in the background, each on its own interval.
+///
+/// Settings are read again before every wait so a change applies without a restart.
+pub fn start_scheduler(app_handle: tauri::AppHandle) {
+    for feed in Feed::ALL {
+        let app_handle = app_handle.clone();
+
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let Some(delay) = next_delay(feed) else {
+                    tokio::time::sleep(DISABLED_FEED_RECHECK).await;
+                    continue;
+                };
+
+                info!("Next refresh of {} in {:?}", feed.name(), delay);
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = WAKE_UP[feed as usize].notified() => {
+                        info!("Refreshing {} ahead of time", feed.name());
+                    }
+                }
+
+                run_feed_refresh(feed, app_handle.clone()).await;
+            }
+        });
+    }
+}
+
+/// Refresh every enabled feed now rather than at its next interval.
+///
+/// The refreshes run in the scheduler loops, a feed being scraped right now
+/// isn't scraped a second time. A loop that isn't waiting yet keeps the wake-up
+/// for its next wait.
+pub fn wake_scheduler() {
+    for wake_up in &WAKE_UP {
+        wake_up.notify_one();
+    }
+}
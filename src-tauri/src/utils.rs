@@ -2,25 +2,25 @@ use std::{
     collections::HashMap,
     error::Error,
     fmt,
-    path::Path,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicU64, Ordering},
         Arc,
     },
     time::Instant,
 };
 
 use anyhow::Result;
-use fit_launcher_config::client::dns::CUSTOM_DNS_CLIENT;
-use futures::future::join_all;
-use lru::LruCache;
-use scraper::{Html, Selector};
+use fit_launcher_config::client::cache::cached_get;
+use fit_launcher_scraping::parser::REPACK_POST_PARSER;
+use fit_launcher_scraping::variants::resolve_variants;
+use futures::future::{AbortHandle, Abortable, BoxFuture, FutureExt, Shared};
 use serde::{Deserialize, Serialize};
 use tauri::{Manager, State, Window};
-use tokio::sync::Mutex;
+use tokio::sync::Notify;
 use tracing::{info, warn};
-// Define a shared boolean flag
-static STOP_FLAG: AtomicBool = AtomicBool::new(false);
+
+use crate::image_cache::{ImageCacheError, ImageCacheService};
+use crate::image_store::IMAGE_STORE;
 
 #[derive(Clone, serde::Serialize)]
 pub struct Payload {
@@ -36,182 +36,177 @@ struct Game {
     href: String,
 }
 
-/// Helper function.
-async fn check_url_status(url: &str) -> anyhow::Result<bool> {
-    let response = CUSTOM_DNS_CLIENT.head(url).send().await.unwrap();
-    Ok(response.status().is_success())
+async fn scrape_image_srcs(url: String) -> Result<Vec<String>> {
+    let body = cached_get(&url).await?.body;
+    let post = REPACK_POST_PARSER.parse_post(&body, &url);
+
+    // Thumbnails without a full size variant are left out
+    Ok(resolve_variants(post.secondary_images)
+        .await
+        .into_iter()
+        .flatten()
+        .collect())
 }
 
-fn parse_image_links(body: &str, start: usize) -> anyhow::Result<Vec<String>> {
-    let document = Html::parse_document(body);
-    let mut images = Vec::new();
+type SharedImageFetch = Shared<BoxFuture<'static, Result<Vec<String>, String>>>;
 
-    for p_index in start..=5 {
-        let selector = Selector::parse(&format!(
-            ".entry-content > p:nth-of-type({}) img[src]",
-            p_index
-        ))
-        .map_err(|_| anyhow::anyhow!("Invalid CSS selector for paragraph {}", p_index))?;
+/// One in-flight image fetch, shared by every view that asked for the same game.
+struct ImageFetchJob {
+    id: u64,
+    fetch: SharedImageFetch,
+    abort_handle: AbortHandle,
+    /// Cancellation signal of each waiting view, keyed by its request id.
+    waiters: HashMap<String, Arc<Notify>>,
+}
 
-        for element in document.select(&selector) {
-            if let Some(src) = element.value().attr("src") {
-                images.push(src.to_string());
-            }
+/// Image fetches in flight, keyed by game link.
+#[derive(Default)]
+pub struct ImageFetchJobs {
+    jobs: std::sync::Mutex<HashMap<String, ImageFetchJob>>,
+    next_id: AtomicU64,
+}
 
-            if images.len() >= 5 {
-                return Ok(images); // Stop once we collect 5 images
+impl ImageFetchJobs {
+    /// Join the fetch of `game_link`, starting it if nobody else is waiting on it.
+    ///
+    /// Cancelling `request_id` only stops this wait, the fetch itself is aborted
+    /// once its last waiter is gone.
+    async fn fetch(&self, game_link: &str, request_id: &str) -> Result<Vec<String>, CustomError> {
+        let cancel = Arc::new(Notify::new());
+
+        let (job_id, fetch) = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let job = jobs.entry(game_link.to_string()).or_insert_with(|| {
+                let (abort_handle, abort_registration) = AbortHandle::new_pair();
+                let fetch =
+                    Abortable::new(scrape_image_srcs(game_link.to_string()), abort_registration)
+                        .map(|result| match result {
+                            Ok(images) => images.map_err(|e| e.to_string()),
+                            Err(_) => Err("Image fetch cancelled".to_string()),
+                        })
+                        .boxed()
+                        .shared();
+
+                ImageFetchJob {
+                    id: self.next_id.fetch_add(1, Ordering::Relaxed),
+                    fetch,
+                    abort_handle,
+                    waiters: HashMap::new(),
+                }
+            });
+
+            job.waiters.insert(request_id.to_string(), cancel.clone());
+            (job.id, job.fetch.clone())
+        };
+
+        tokio::select! {
+            result = fetch => {
+                // The first waiter to see the fetch end retires the job, a newer
+                // job for the same link is left alone.
+                let mut jobs = self.jobs.lock().unwrap();
+                if jobs.get(game_link).is_some_and(|job| job.id == job_id) {
+                    jobs.remove(game_link);
+                }
+                result.map_err(|message| CustomError { message })
             }
+            _ = cancel.notified() => Err(CustomError {
+                message: format!("Image fetch of {} cancelled by {}", game_link, request_id),
+            }),
         }
     }
 
-    Ok(images)
-}
-
-async fn process_image_link(src_link: String) -> anyhow::Result<String> {
-    if src_link.contains("jpg.240p.") {
-        let primary_image = src_link.replace("240p", "1080p");
-        if check_url_status(&primary_image).await.unwrap_or(false) {
-            return Ok(primary_image);
+    /// Cancel the wait of one view, or of every view of `game_link` without `request_id`.
+    fn cancel(&self, game_link: &str, request_id: Option<&str>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(job) = jobs.get_mut(game_link) else {
+            return;
+        };
+
+        match request_id {
+            Some(request_id) => {
+                if let Some(cancel) = job.waiters.remove(request_id) {
+                    cancel.notify_one();
+                }
+            }
+            None => job
+                .waiters
+                .drain()
+                .for_each(|(_, cancel)| cancel.notify_one()),
         }
 
-        let fallback_image = primary_image.replace("jpg.1080p.", "");
-        if check_url_status(&fallback_image).await.unwrap_or(false) {
-            return Ok(fallback_image);
+        if job.waiters.is_empty() {
+            job.abort_handle.abort();
+            jobs.remove(game_link);
         }
     }
 
-    Err(anyhow::anyhow!("No valid image found for {}", src_link))
-}
-
-async fn fetch_image_links(body: &str) -> anyhow::Result<Vec<String>> {
-    let initial_images = parse_image_links(body, 3)?;
-
-    // Spawn tasks for each image processing
-    let tasks: Vec<_> = initial_images
-        .into_iter()
-        .map(|img_link| {
-            tokio::task::spawn(async move {
-                match process_image_link(img_link).await {
-                    Ok(img) => Some(img),
-                    Err(_) => None,
-                }
-            })
-        })
-        .collect();
-
-    let results = join_all(tasks).await;
-
-    // Collect successful, non-None results
-    let mut processed = Vec::new();
-    results.into_iter().for_each(|res| {
-        if let Ok(Some(img)) = res {
-            processed.push(img);
+    fn cancel_all(&self) {
+        let links: Vec<String> = self.jobs.lock().unwrap().keys().cloned().collect();
+        for game_link in links {
+            self.cancel(&game_link, None);
         }
-    });
-
-    Ok(processed)
-}
-
-async fn scrape_image_srcs(url: &str) -> Result<Vec<String>> {
-    if STOP_FLAG.load(Ordering::Relaxed) {
-        return Err(anyhow::anyhow!("Cancelled the Event..."));
     }
-
-    let body = CUSTOM_DNS_CLIENT.get(url).send().await?.text().await?;
-    let images = fetch_image_links(&body).await?;
-
-    Ok(images)
 }
 
-// Cache with a capacity of 100
-type ImageCache = Arc<Mutex<LruCache<String, Vec<String>>>>;
-
+/// Cancel image fetches: one view's request, every request for a game, or everything.
 #[tauri::command]
-pub async fn stop_get_games_images() {
-    STOP_FLAG.store(true, Ordering::Relaxed);
-}
-
-#[derive(Serialize, Deserialize, Clone)]
-struct CachedGameImages {
-    game_link: String,
-    images: Vec<String>,
+pub async fn stop_get_games_images(
+    image_jobs: State<'_, ImageFetchJobs>,
+    game_link: Option<String>,
+    request_id: Option<String>,
+) -> Result<(), CustomError> {
+    match game_link {
+        Some(game_link) => image_jobs.cancel(&game_link, request_id.as_deref()),
+        None => image_jobs.cancel_all(),
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn get_games_images(
-    app_handle: tauri::AppHandle,
     game_link: String,
-    image_cache: State<'_, ImageCache>,
+    request_id: Option<String>,
+    image_cache: State<'_, ImageCacheService>,
+    image_jobs: State<'_, ImageFetchJobs>,
 ) -> Result<(), CustomError> {
     let now = Instant::now();
 
-    let cache_file_path = app_handle
-        .path()
-        .app_cache_dir()
-        .unwrap_or_default()
-        .join("image_cache.json");
-
-    // Acquire the lock and merge cache from file
-    let mut cache = image_cache.lock().await;
-    merge_cache_from_file(&cache_file_path, &mut cache).await?;
-
-    // Check if the game link is already cached
-    if let Some(image_list) = cache.get(&game_link) {
-        if !image_list.is_empty() {
-            // Images already exist in cache, no need to fetch
-            return Ok(());
-        }
-        warn!(
-            "The list of {} is empty, it will get images again",
-            &game_link
-        );
+    // An empty list was a failed scrape, it is fetched again
+    if image_cache.images(&game_link).await.is_some() {
+        return Ok(());
     }
 
-    drop(cache); // Release lock before performing network operations
-
-    // Fetch image sources
-    let image_srcs = scrape_image_srcs(&game_link).await?;
-
-    // Save fetched data back to the cache
-    let mut cache = image_cache.lock().await;
-    cache.put(game_link.clone(), image_srcs.clone());
-    save_cache_to_file(&cache_file_path, &cache).await?;
-
-    info!("Time elapsed to find images: {:?}", now.elapsed());
-
-    Ok(())
-}
-
-async fn merge_cache_from_file(
-    cache_file_path: &Path,
-    cache: &mut LruCache<String, Vec<String>>,
-) -> Result<()> {
-    if let Ok(data) = tokio::fs::read_to_string(cache_file_path).await {
-        if let Ok(loaded_cache) = serde_json::from_str::<HashMap<String, Vec<String>>>(&data) {
-            for (key, value) in loaded_cache {
-                cache.put(key, value);
+    // Fetch image sources, sharing the fetch with any other view asking for the same game
+    let request_id = request_id.unwrap_or_else(|| {
+        format!(
+            "anonymous-{}",
+            image_jobs.next_id.fetch_add(1, Ordering::Relaxed)
+        )
+    });
+    let image_srcs = image_jobs.fetch(&game_link, &request_id).await?;
+
+    image_cache
+        .insert(game_link.clone(), image_srcs.clone())
+        .await?;
+
+    // Keep the screenshots themselves too, the page can then be shown offline
+    tauri::async_runtime::spawn(async move {
+        for image_src in image_srcs {
+            let prepared = match IMAGE_STORE.store_url(&image_src).await {
+                Ok(hash) => IMAGE_STORE.prepare(&hash).await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = prepared {
+                warn!("Failed to store {}: {}", image_src, e);
             }
         }
-    }
-    Ok(())
-}
-
-async fn save_cache_to_file(
-    cache_file_path: &Path,
-    cache: &LruCache<String, Vec<String>>,
-) -> Result<()> {
-    let cache_as_hashmap: HashMap<String, Vec<String>> =
-        cache.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
-
-    let cache_data = serde_json::to_string_pretty(&cache_as_hashmap)
-        .map_err(|e| anyhow::anyhow!("Failed to serialize cache: {}", e))?;
+    });
 
-    tokio::fs::write(cache_file_path, cache_data)
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to write cache to file: {}", e))?;
+    info!("Time elapsed to find images: {:?}", now.elapsed());
 
     Ok(())
 }
+
 //Always serialize returns...
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FileContent {
@@ -247,6 +242,14 @@ impl From<anyhow::Error> for CustomError {
     }
 }
 
+impl From<ImageCacheError> for CustomError {
+    fn from(error: ImageCacheError) -> Self {
+        CustomError {
+            message: error.to_string(),
+        }
+    }
+}
+
 impl From<std::io::Error> for CustomError {
     fn from(error: std::io::Error) -> Self {
         CustomError {
@@ -21,6 +21,7 @@ const AddLocalGamePopUp = ({ infoTitle, infoFooter, action }) => {
     function transformGameData(input) {
       const transformedData = {
         torrentExternInfo: {
+          id: input.id || "",
           title: input.title || "",
           img: input.img || "",
           desc: input.desc || "",
@@ -29,6 +30,9 @@ const AddLocalGamePopUp = ({ infoTitle, infoFooter, action }) => {
           tag: input.tag || "",
         },
         torrentIdx: extractTorrentIdx(input.magnetlink) || "",
+        // Kept to tell when the site gets a newer repack of this game.
+        installedVersion: input.metadata?.version || "",
+        postUrl: input.href || "",
         torrentOutputFolder: "",
         torrentDownloadFolder: "",
         torrentFileList: ["", ""],
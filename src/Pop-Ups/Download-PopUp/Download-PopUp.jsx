@@ -352,7 +352,11 @@ const LastStep = ({ closePopup, gameMagnet, downloadGamePath, externFullGameInfo
             url: gameMagnet,
             opts: { only_files: completeIDFileList(), overwrite: true },
         });
-        let installationSettings = await invoke("get_installation_settings");
+        // An unreadable settings file is reported by the backend, the download goes on without the extra installs.
+        let installationSettings = await invoke("get_installation_settings").catch((error) => {
+            console.error("Error getting installation settings: ", error);
+            return null;
+        });
         if (installationSettings) {
             const checkedOptions = [];
             //TODO: Remove this spaghetti code and let the backend handle this depending on the result of the settings_initialization.rs get_installation_settings() function.
@@ -380,7 +384,7 @@ const LastStep = ({ closePopup, gameMagnet, downloadGamePath, externFullGameInfo
             mainTorrentDetails().output_folder,
             downloadGamePath,
             checkboxesListComponents(),
-            installationSettings.two_gb_limit
+            installationSettings?.two_gb_limit
         );
         setGameStartedDownload(true);
         setLoading(false);
@@ -2,6 +2,7 @@ import { createSignal, createEffect } from 'solid-js';
 import './Slider.css';
 import { useNavigate } from '@solidjs/router';
 import { setDownloadGamePageInfo } from '../functions/dataStoreGlobal';
+import StoredImage from '../UI/StoredImage/StoredImage';
 
 
 const Slider = (props) => {
@@ -112,8 +113,9 @@ const Slider = (props) => {
                         <div className="slider-image-container" key={index} onClick={() => {
                             handleImageClick(titles[index], filePath, hrefs[index])
                         }}>
-                            <img
+                            <StoredImage
                                 src={image}
+                                width={375 * 0.5}
                                 alt={Array.isArray(titles) ? titles[index] : titles}
                                 filepath={filePath}
                                 style={{ width: '100%', height: '100%', objectFit: 'cover', cursor: 'pointer' }}
@@ -0,0 +1,50 @@
+import { createEffect, createResource, createSignal, on, splitProps } from "solid-js";
+import { invoke } from "@tauri-apps/api/core";
+import { storedImageSrc } from "../../functions/imageStore";
+
+/**
+ * Picture from the image store, loaded as the thumbnail closest to `width` (in CSS pixels).
+ * Its blurred placeholder is drawn behind it until the thumbnail has loaded.
+ */
+const StoredImage = (props) => {
+    const [local, others] = splitProps(props, ["src", "width", "style", "onLoad"]);
+    const [loaded, setLoaded] = createSignal(false);
+
+    const [preview] = createResource(() => local.src, async (url) => {
+        try {
+            return await invoke("get_image_preview", { url });
+        } catch (error) {
+            console.warn(`No preview for ${url}:`, error);
+            return null;
+        }
+    });
+
+    // The same element is reused when the picture changes, show the new placeholder.
+    createEffect(on(() => local.src, () => setLoaded(false), { defer: true }));
+
+    const placeholderStyle = () => {
+        const placeholder = preview()?.placeholder;
+        if (loaded() || !placeholder) {
+            return {};
+        }
+        return {
+            "background-image": `url(${placeholder})`,
+            "background-size": "cover",
+            "background-position": "center",
+        };
+    };
+
+    return (
+        <img
+            {...others}
+            src={storedImageSrc(local.src, local.width * window.devicePixelRatio)}
+            style={{ ...placeholderStyle(), ...local.style }}
+            onLoad={(event) => {
+                setLoaded(true);
+                local.onLoad?.(event);
+            }}
+        />
+    );
+};
+
+export default StoredImage;
@@ -18,6 +18,9 @@ function addGlobalTorrentsInfo(torrentExternInfo, torrentIdx, torrentOutputFolde
         {
             torrentExternInfo: torrentExternInfo,
             torrentIdx: torrentIdx,
+            // Copied into the library once the download finishes, it tells when the site gets a newer repack.
+            installedVersion: torrentExternInfo?.metadata?.version || "",
+            postUrl: torrentExternInfo?.href || "",
             torrentOutputFolder: torrentOutputFolder,
             torrentDownloadFolder: torrentDownloadFolder,
             torrentFileList: torrentFileList,
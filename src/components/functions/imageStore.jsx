@@ -0,0 +1,20 @@
+import { convertFileSrc } from "@tauri-apps/api/core";
+
+// `fitimg://localhost/`, or `https://fitimg.localhost/` on Windows.
+const imageStoreBase = convertFileSrc("", "fitimg");
+
+/**
+ * URL serving the picture at `url` from the image store, downloaded on first use.
+ * `width` asks for the smallest thumbnail at least that wide.
+ */
+export function storedImageSrc(url, width) {
+    if (!url) {
+        return url;
+    }
+
+    const query = new URLSearchParams({ url });
+    if (width) {
+        query.set("w", Math.ceil(width));
+    }
+    return `${imageStoreBase}?${query}`;
+}
@@ -4,6 +4,7 @@ import './Download-Game-UUID.css';
 import { mkdir, readTextFile, writeTextFile } from "@tauri-apps/plugin-fs";
 import { appCacheDir, appDataDir } from "@tauri-apps/api/path";
 import { downloadGamePageInfo } from "../../components/functions/dataStoreGlobal";
+import { storedImageSrc } from "../../components/functions/imageStore";
 import { createMemoryHistory, useLocation, useNavigate } from "@solidjs/router";
 import { render } from "solid-js/web";
 import { path } from "@tauri-apps/api";
@@ -28,6 +29,8 @@ const DownloadGameUUIDPage = () => {
     const [loading, setLoading] = createSignal(true);
     const [additionalImages, setAdditionalImages] = createSignal([])
     const [cacheDirPath, setCacheDirPath] = createSignal('');
+    // Identifies this page's image request so leaving it only cancels our own wait
+    const imagesRequestId = crypto.randomUUID();
     const [dirPath, setDirPath] = createSignal('');
     const navigate = useNavigate();
 
@@ -212,7 +215,7 @@ const DownloadGameUUIDPage = () => {
             let cacheLoaded = await loadFromCache();
             if (!cacheLoaded) {
                 console.log("reloading")
-                await invoke('get_games_images', { gameLink: gameHref });
+                await invoke('get_games_images', { gameLink: gameHref, requestId: imagesRequestId });
 
                 const retryInterval = setInterval(async () => {
                     cacheLoaded = await loadFromCache();
@@ -231,6 +234,7 @@ const DownloadGameUUIDPage = () => {
     onCleanup(() => {
         clearInterval(backgroundCycleIntervalID);
         clearTimeout(imagesCheckingTimeoutID);
+        invoke('stop_get_games_images', { gameLink: gameHref, requestId: imagesRequestId });
     });
 
 
@@ -328,7 +332,7 @@ const DownloadGameUUIDPage = () => {
                         <>
                             <div className="download-game-background"
                                 style={{
-                                    'background-image': `url(${additionalImages()[currentImageIndex()]})`,
+                                    'background-image': `url(${storedImageSrc(additionalImages()[currentImageIndex()])})`,
                                     'background-size': 'cover',
                                     'background-position': 'center',
                                     '--webkit-mask-image': 'linear-gradient(to bottom, rgba(0, 0, 0, 1) 0%, rgba(0, 0, 0, 1) 80%, rgba(0, 0, 0, 0) 100%)',
@@ -1,6 +1,7 @@
 import { createEffect, onMount, createSignal, onCleanup, createResource } from 'solid-js';
 import { readTextFile } from '@tauri-apps/plugin-fs';
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from '@tauri-apps/api/event';
 import { createWorker } from '@solid-primitives/workers';
 import { appDataDir } from '@tauri-apps/api/path';
 import './Popular-Games.css'
@@ -10,6 +11,7 @@ import { colorCache, setColorCache } from '../../../../components/functions/data
 import { makePersisted } from '@solid-primitives/storage';
 import { useNavigate } from '@solidjs/router';
 import { setDownloadGamePageInfo } from '../../../../components/functions/dataStoreGlobal';
+import StoredImage from '../../../../components/UI/StoredImage/StoredImage';
 import { path } from '@tauri-apps/api';
 
 const appDir = await appDataDir()
@@ -82,12 +84,16 @@ function PopularGames() {
 
             setFilteredImages(popularGamesData);
 
-            let display_settings = await invoke('get_gamehub_settings');
-            setDisplaySettings(display_settings)
-
         } catch (error) {
             console.error("Error parsing game data : ", error)
         }
+
+        // An unreadable settings file is reported by the backend, the games are shown without colors.
+        try {
+            setDisplaySettings(await invoke('get_gamehub_settings'))
+        } catch (error) {
+            console.error("Error getting gamehub settings : ", error)
+        }
     })
 
     const [fetchColors] = createResource(numberOfGames, async (gamesCount) => {
@@ -152,17 +158,32 @@ function PopularGames() {
     }
 
     async function fetchDominantColors() {
-        try {
-            const images = imagesObject();
-            const imageUrls = images?.map(img => img.img);
+        const images = imagesObject();
+        const imageUrls = images?.map(img => img.img);
+
+        // Colors show up as soon as they are extracted, the slowest picture doesn't hold the others back.
+        const unlisten = await listen("dominant-color-extracted", (event) => {
+            const { index, url, color } = event.payload;
+            if (color && imageUrls?.[index] === url) {
+                setColorCache(index, color);
+            }
+        });
 
+        try {
             // TODO: Fix callback issue when user reload the page, could also prohibit the user completely from reloading the window.
-            const colorStrings = await invoke("check_dominant_color_vec", { listImages: imageUrls });
+            const extractedColors = await invoke("check_dominant_color_vec", { listImages: imageUrls });
 
-            setColorCache(colorStrings);
+            extractedColors.forEach(({ url, error }) => {
+                if (error) {
+                    console.warn(`No dominant color for ${url}:`, error);
+                }
+            });
+            setColorCache(extractedColors.map(({ color }) => color));
 
         } catch (error) {
             console.error("Error fetching dominant colors:", error);
+        } finally {
+            unlisten();
         }
     }
 
@@ -237,7 +258,7 @@ function PopularGames() {
 
         } else {
 
-            if (displaySettings().auto_get_colors_popular_games) {
+            if (displaySettings()?.auto_get_colors_popular_games) {
                 fetchDominantColors();
             }
         }
@@ -312,8 +333,9 @@ function PopularGames() {
             ) : (
               <div className="main-game-container">
                 <div className="main-game-image-zoomed-in">
-                  <img
+                  <StoredImage
                     src={imagesObject()?.[selectedGame()]?.img}
+                    width={window.innerWidth * 0.48}
                     alt="game-background"
                     className="game-image-background"
                     style={{